@@ -1,64 +1,144 @@
+use std::collections::VecDeque;
+
 advent_of_code::solution!(1);
 
 pub fn part_one(input: &str) -> Option<u32> {
-    Some(input.lines().map(read_digits).sum())
+    let scanner = Scanner::new(DIGITS);
+    Some(
+        input
+            .lines()
+            .map(|line| scanner.calibration_value(line))
+            .sum(),
+    )
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    Some(input.lines().map(read_words).sum())
+    let scanner = Scanner::new(DIGITS.iter().chain(WORDS));
+    Some(
+        input
+            .lines()
+            .map(|line| scanner.calibration_value(line))
+            .sum(),
+    )
 }
 
-fn read_digits(input: &str) -> u32 {
-    let (first, last) = input.chars().fold((None, None), |acc, c| {
-        if let Some(digit) = c.to_digit(10) {
-            (acc.0.or(Some(digit)), Some(digit))
-        } else {
-            acc
-        }
-    });
+const DIGITS: &[(&str, u32)] = &[
+    ("0", 0),
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+];
 
-    match (first, last) {
-        (Some(first), Some(last)) => first * 10 + last,
-        _ => 0,
-    }
-}
+const WORDS: &[(&str, u32)] = &[
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
 
-fn read_words(input: &str) -> u32 {
-    let numbers = [
-        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-    ];
+/// A word of the vocabulary ending at some state, as `(digit, length)`.
+type Output = Option<(u32, usize)>;
 
-    let mut res: (u32, u32) = (0, 0);
+/// An Aho-Corasick automaton over a vocabulary of words standing for digits. Every state has
+/// a transition for every byte, so a line is scanned one byte at a time without backtracking,
+/// and overlapping words like `oneight` are all found.
+struct Scanner {
+    transitions: Vec<[usize; 256]>,
+    /// Of all words ending at each state, the longest one, which starts earliest.
+    longest: Vec<Output>,
+    /// Of all words ending at each state, the shortest one, which starts latest.
+    shortest: Vec<Output>,
+}
 
-    let mut left = input.len();
-    let mut right = 0;
-    for (i, number) in numbers.iter().enumerate() {
-        if let Some(idx) = input.find(number) {
-            if idx < left {
-                left = idx;
-                res.0 = (i + 1) as u32;
+impl Scanner {
+    /// Builds the automaton for `(word, digit)` pairs. Empty words are ignored.
+    fn new<'a>(vocabulary: impl IntoIterator<Item = &'a (&'a str, u32)>) -> Self {
+        // a trie first, where 0 is both the root and "no child yet"
+        let mut transitions = vec![[0; 256]];
+        let mut words = vec![None];
+        for (word, digit) in vocabulary {
+            if word.is_empty() {
+                continue;
+            }
+            let mut state = 0;
+            for byte in word.bytes() {
+                if transitions[state][byte as usize] == 0 {
+                    transitions.push([0; 256]);
+                    words.push(None);
+                    transitions[state][byte as usize] = transitions.len() - 1;
+                }
+                state = transitions[state][byte as usize];
             }
+            words[state] = Some((*digit, word.len()));
         }
-        if let Some(idx) = input.rfind(number) {
-            if idx >= right {
-                right = idx;
-                res.1 = (i + 1) as u32;
+
+        // then breadth first, so a state's failure link is done before the state itself
+        let mut longest = words.clone();
+        let mut shortest = words.clone();
+        let mut fail = vec![0; transitions.len()];
+        let mut queue = VecDeque::from([0]);
+        while let Some(state) = queue.pop_front() {
+            let fallbacks = transitions[fail[state]];
+            for (byte, fallback) in fallbacks.into_iter().enumerate() {
+                let child = transitions[state][byte];
+                if child == 0 {
+                    transitions[state][byte] = fallback;
+                    continue;
+                }
+                fail[child] = if state == 0 { 0 } else { fallback };
+                longest[child] = words[child].or(longest[fail[child]]);
+                shortest[child] = shortest[fail[child]].or(words[child]);
+                queue.push_back(child);
             }
         }
-    }
 
-    if let Some(idx) = input.find(|c: char| c.is_ascii_digit()) {
-        if idx < left {
-            res.0 = input.chars().nth(idx).unwrap().to_digit(10).unwrap();
+        Self {
+            transitions,
+            longest,
+            shortest,
         }
     }
-    if let Some(idx) = input.rfind(|c: char| c.is_ascii_digit()) {
-        if idx >= right {
-            res.1 = input.chars().nth(idx).unwrap().to_digit(10).unwrap();
+
+    /// The digits of the first and last words in `line`, ordered by where they start.
+    fn first_and_last(&self, line: &str) -> Option<(u32, u32)> {
+        let mut state = 0;
+        let mut first: Option<(usize, u32)> = None;
+        let mut last: Option<(usize, u32)> = None;
+        for (end, byte) in line.bytes().enumerate() {
+            state = self.transitions[state][byte as usize];
+            if let Some((digit, length)) = self.longest[state] {
+                let start = end + 1 - length;
+                if first.is_none_or(|(first_start, _)| start < first_start) {
+                    first = Some((start, digit));
+                }
+            }
+            if let Some((digit, length)) = self.shortest[state] {
+                let start = end + 1 - length;
+                if last.is_none_or(|(last_start, _)| start >= last_start) {
+                    last = Some((start, digit));
+                }
+            }
         }
+        Some((first?.1, last?.1))
     }
 
-    res.0 * 10 + res.1
+    fn calibration_value(&self, line: &str) -> u32 {
+        match self.first_and_last(line) {
+            Some((first, last)) => first * 10 + last,
+            None => 0,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -78,4 +158,37 @@ mod tests {
         ));
         assert_eq!(result, Some(281));
     }
+
+    #[test]
+    fn test_overlapping_words() {
+        let scanner = Scanner::new(DIGITS.iter().chain(WORDS));
+        let values = [
+            "oneight",
+            "twone",
+            "eighthree",
+            "sevenine",
+            "nineight2oneight",
+        ]
+        .map(|line| scanner.calibration_value(line));
+        assert_eq!(values, [18, 21, 83, 79, 98]);
+
+        // a single match is both first and last, and no match counts for nothing
+        assert_eq!(scanner.calibration_value("treb7uchet"), 77);
+        assert_eq!(scanner.calibration_value("xtwxnx"), 0);
+        assert_eq!(scanner.calibration_value(""), 0);
+    }
+
+    #[test]
+    fn test_custom_vocabulary() {
+        let scanner = Scanner::new(&[("un", 1), ("deux", 2), ("trois", 3), ("", 4)]);
+        assert_eq!(scanner.calibration_value("undeuxtrois"), 13);
+        assert_eq!(scanner.calibration_value("one2three"), 0);
+
+        // `bc` ends first, but `abcd` starts first
+        let scanner = Scanner::new(&[("abcd", 1), ("bc", 2)]);
+        assert_eq!(scanner.first_and_last("xabcdx"), Some((1, 2)));
+        // `cd` starts later but ends at the same place as `abcd`
+        let scanner = Scanner::new(&[("abcd", 1), ("cd", 2)]);
+        assert_eq!(scanner.first_and_last("abcd"), Some((1, 2)));
+    }
 }
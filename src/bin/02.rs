@@ -1,74 +1,117 @@
-use std::collections::HashMap;
-
-use regex::Regex;
+use advent_of_code::helpers::parse::{Cursor, ParseResult};
 
 advent_of_code::solution!(2);
 
 pub fn part_one(input: &str) -> Option<u32> {
-    Some(input.lines().map(parse_game).map(check_game_valid).sum())
+    report(possible_games(input, &CubeSet::BAG))
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    Some(input.lines().map(parse_game).map(get_power_of_cubes).sum())
+    report(games(input).map(|game| Ok(game?.min_bag.power())).sum())
 }
 
-#[derive(Debug)]
-struct Game {
-    id: u32,
-    attempts: Vec<HashMap<String, u32>>,
+fn report(result: ParseResult<u32>) -> Option<u32> {
+    match result {
+        Ok(sum) => Some(sum),
+        Err(error) => {
+            eprintln!("error: {error}");
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CubeSet {
+    red: u32,
+    green: u32,
+    blue: u32,
 }
 
-fn parse_game(input: &str) -> Game {
-    let re = Regex::new(r"^Game (?P<game_id>\d+): (?P<attempts>.*)$").unwrap();
-    let caps = re.captures(input).unwrap();
-    let attempts: Vec<HashMap<String, u32>> = caps["attempts"]
-        .split(';')
-        .map(|attempt| {
-            attempt
-                .split(',')
-                .fold(HashMap::new(), |mut color_map, play| {
-                    let res: Vec<&str> = play.trim().splitn(2, ' ').collect();
-                    color_map.insert(res[1].to_string(), res[0].parse().unwrap());
-                    color_map
-                })
-        })
-        .collect();
-    Game {
-        id: caps["game_id"].parse::<u32>().unwrap(),
-        attempts,
+impl CubeSet {
+    /// What the Elf loaded the bag with for the first question.
+    const BAG: Self = Self {
+        red: 12,
+        green: 13,
+        blue: 14,
+    };
+
+    /// The smallest set containing both, colour by colour.
+    fn max(self, other: Self) -> Self {
+        Self {
+            red: self.red.max(other.red),
+            green: self.green.max(other.green),
+            blue: self.blue.max(other.blue),
+        }
+    }
+
+    fn fits_in(&self, bag: &Self) -> bool {
+        self.red <= bag.red && self.green <= bag.green && self.blue <= bag.blue
+    }
+
+    fn power(&self) -> u32 {
+        self.red * self.green * self.blue
     }
 }
 
-fn attempt_is_possible(input: HashMap<String, u32>) -> bool {
-    let max_colors: HashMap<&str, u32> = [("red", 12), ("green", 13), ("blue", 14)]
-        .iter()
-        .cloned()
-        .collect();
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Game {
+    id: u32,
+    /// The fewest cubes of each colour the bag could have held for every draw.
+    min_bag: CubeSet,
+}
 
-    input
-        .iter()
-        .all(|(color, value)| *value <= *max_colors.get(color.as_str()).unwrap_or(&0))
+/// Sums the ids of the games that could have been played with `bag`.
+fn possible_games(input: &str, bag: &CubeSet) -> ParseResult<u32> {
+    games(input)
+        .filter(|game| game.as_ref().map_or(true, |game| game.min_bag.fits_in(bag)))
+        .map(|game| Ok(game?.id))
+        .sum()
 }
 
-fn check_game_valid(input: Game) -> u32 {
-    match input.attempts.into_iter().all(attempt_is_possible) {
-        true => input.id,
-        false => 0,
-    }
+/// Parses one game per line as it goes, without allocating.
+fn games(input: &str) -> impl Iterator<Item = ParseResult<Game>> + '_ {
+    let mut cursor = Cursor::new(input);
+    std::iter::from_fn(move || {
+        cursor.take_while(|c| c == '\r' || c == '\n');
+        (!cursor.is_empty()).then(|| parse_game(&mut cursor))
+    })
 }
 
-fn get_power_of_cubes(input: Game) -> u32 {
-    let max_colors: HashMap<&str, u32> = input
-        .attempts
-        .iter()
-        .flat_map(|attempt| attempt.iter())
-        .fold(HashMap::new(), |mut acc, (color, value)| {
-            let max_value = *acc.get(color.as_str()).unwrap_or(&0);
-            acc.insert(color, max_value.max(*value));
-            acc
-        });
-
-    max_colors.values().product()
+fn parse_game(cursor: &mut Cursor) -> ParseResult<Game> {
+    cursor.tag("Game ")?;
+    let id = cursor.int()?;
+    cursor.tag(":")?;
+
+    // only the largest count of each colour matters, so draws needn't be told apart
+    let mut min_bag = CubeSet::default();
+    loop {
+        cursor.spaces();
+        let count = cursor.int()?;
+        cursor.spaces();
+        let colour_start = *cursor;
+        let cubes = match cursor.word()? {
+            "red" => CubeSet {
+                red: count,
+                ..CubeSet::default()
+            },
+            "green" => CubeSet {
+                green: count,
+                ..CubeSet::default()
+            },
+            "blue" => CubeSet {
+                blue: count,
+                ..CubeSet::default()
+            },
+            colour => return Err(colour_start.error(format!("unknown colour {colour:?}"))),
+        };
+        min_bag = min_bag.max(cubes);
+
+        match cursor.peek() {
+            Some(',' | ';') => cursor.take_while(|c| c == ',' || c == ';'),
+            None | Some('\r' | '\n') => return Ok(Game { id, min_bag }),
+            Some(_) => return Err(cursor.error("expected ',', ';' or the end of the line")),
+        };
+    }
 }
 
 #[cfg(test)]
@@ -86,4 +129,30 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(2286));
     }
+
+    #[test]
+    fn test_bags() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let game = games(&input).nth(2).unwrap();
+        assert_eq!(
+            game.map(|game| game.min_bag),
+            Ok(CubeSet {
+                red: 20,
+                green: 13,
+                blue: 6,
+            })
+        );
+
+        let plenty = CubeSet {
+            red: 20,
+            green: 20,
+            blue: 20,
+        };
+        assert_eq!(possible_games(&input, &plenty), Ok(15));
+        assert_eq!(possible_games(&input, &CubeSet::default()), Ok(0));
+
+        let error = possible_games("Game 1: 3 blue\nGame 2: 3 purple\n", &plenty).unwrap_err();
+        assert_eq!(error.to_string(), "2:11: unknown colour \"purple\"");
+        assert_eq!(part_one("Game 1: 3 blue 4 red"), None);
+    }
 }
@@ -1,131 +1,116 @@
-use advent_of_code::helpers::matrix::{read_matrix, Matrix};
+use std::ops::Range;
+
+use itertools::Itertools;
 
 advent_of_code::solution!(3);
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let mut grid_part_1: Matrix = read_matrix(input);
-    let part1 = count_all_part_numbers(&mut grid_part_1);
-    Some(part1)
+    let schematic = Schematic::parse(input);
+    let part_numbers = schematic.numbers_touching(|_| true);
+    Some(part_numbers.iter().map(|number| number.value).sum())
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let mut grid_part_2: Matrix = read_matrix(input);
-    let part2 = count_gear_ratios(&mut grid_part_2);
-    Some(part2)
+    let schematic = Schematic::parse(input);
+    Some(schematic.gear_ratios().sum())
 }
 
-fn extract_number(row: &mut Vec<char>, col: usize) -> u32 {
-    let mut left: i32 = col as i32;
-    while left >= 0 && row[left as usize].is_ascii_digit() {
-        left -= 1;
-    }
-    let mut right = col;
-    while right < row.len() && row[right].is_ascii_digit() {
-        right += 1;
-    }
-
-    let number = row[((left + 1) as usize)..right]
-        .iter()
-        .collect::<String>()
-        .parse::<u32>()
-        .unwrap();
-
-    row[((left + 1) as usize)..right]
-        .iter_mut()
-        .for_each(|c| *c = '.');
-
-    number
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Number {
+    value: u32,
+    row: usize,
+    cols: Range<usize>,
 }
 
-fn check_adjascent_and_diagonals(row: &mut Vec<char>, col: usize) -> Vec<u32> {
-    let mut numbers: Vec<u32> = Vec::new();
-    if row[col].is_ascii_digit() {
-        numbers.push(extract_number(row, col));
-    }
-    if col > 0 && row[col - 1].is_ascii_digit() {
-        numbers.push(extract_number(row, col - 1));
-    }
-    if col < row.len() - 1 && row[col + 1].is_ascii_digit() {
-        numbers.push(extract_number(row, col + 1));
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Symbol {
+    kind: char,
+    row: usize,
+    col: usize,
+}
 
-    numbers
+/// The engine schematic, with `adjacent[symbol]` listing the indices of the numbers that touch
+/// `symbols[symbol]`, diagonals included.
+struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    adjacent: Vec<Vec<usize>>,
 }
 
-fn count_all_part_numbers(input: &mut Matrix) -> u32 {
-    let mut result = 0;
-    for row in 0..input.len() {
-        let mut row_numbers: Vec<u32> = Vec::new();
-        for col in 0..input[row].len() {
-            if !input[row][col].is_ascii_digit() && input[row][col] != '.' {
-                if row > 0 {
-                    row_numbers
-                        .append(&mut check_adjascent_and_diagonals(&mut input[row - 1], col));
+impl Schematic {
+    fn parse(input: &str) -> Self {
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        for (row, line) in input.lines().enumerate() {
+            let mut chars = line.char_indices().peekable();
+            while let Some((col, c)) = chars.next() {
+                if let Some(mut value) = c.to_digit(10) {
+                    let mut end = col + 1;
+                    while let Some(digit) = chars.peek().and_then(|(_, c)| c.to_digit(10)) {
+                        value = value * 10 + digit;
+                        end += 1;
+                        chars.next();
+                    }
+                    numbers.push(Number {
+                        value,
+                        row,
+                        cols: col..end,
+                    });
+                } else if c != '.' {
+                    symbols.push(Symbol { kind: c, row, col });
                 }
-                if row < input[row].len() - 1 {
-                    row_numbers
-                        .append(&mut check_adjascent_and_diagonals(&mut input[row + 1], col));
-                }
-
-                row_numbers.append(&mut check_adjascent_and_diagonals(&mut input[row], col));
             }
         }
-        result += row_numbers.iter().sum::<u32>();
-    }
-
-    result
-}
-
-fn count_adjacent(input: &Matrix, row: usize, col: usize) -> u32 {
-    let mut result = 0;
 
-    if row > 0 && input[row - 1][col].is_ascii_digit() {
-        result += 1;
-    } else {
-        if col > 0 && input[row - 1][col - 1].is_ascii_digit() {
-            result += 1
-        }
-        if col < input[row - 1].len() - 1 && input[row - 1][col + 1].is_ascii_digit() {
-            result += 1
-        }
-    }
-    if col > 0 && input[row][col - 1].is_ascii_digit() {
-        result += 1;
-    }
-    if col < input[row].len() - 1 && input[row][col + 1].is_ascii_digit() {
-        result += 1;
-    }
-    if row < input.len() - 1 && input[row + 1][col].is_ascii_digit() {
-        result += 1;
-    } else {
-        if col > 0 && input[row + 1][col - 1].is_ascii_digit() {
-            result += 1
+        // every cell covered by a number points back at it
+        let cols = input.lines().map(str::len).max().unwrap_or(0);
+        let mut cells = vec![None; input.lines().count() * cols];
+        for (index, number) in numbers.iter().enumerate() {
+            for col in number.cols.clone() {
+                cells[number.row * cols + col] = Some(index);
+            }
         }
-        if col < input[row].len() - 1 && input[row + 1][col + 1].is_ascii_digit() {
-            result += 1
+        let adjacent = symbols
+            .iter()
+            .map(|symbol| {
+                (symbol.row.saturating_sub(1)..=symbol.row + 1)
+                    .cartesian_product(symbol.col.saturating_sub(1)..=symbol.col + 1)
+                    .filter(|(_, col)| *col < cols)
+                    .filter_map(|(row, col)| *cells.get(row * cols + col)?)
+                    .sorted()
+                    .dedup()
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            numbers,
+            symbols,
+            adjacent,
         }
     }
 
-    result
-}
+    /// Numbers touching at least one symbol of a kind picked by `is_kind`, each once.
+    fn numbers_touching(&self, is_kind: impl Fn(char) -> bool) -> Vec<&Number> {
+        self.symbols
+            .iter()
+            .zip(&self.adjacent)
+            .filter(|(symbol, _)| is_kind(symbol.kind))
+            .flat_map(|(_, numbers)| numbers)
+            .sorted()
+            .dedup()
+            .map(|index| &self.numbers[*index])
+            .collect()
+    }
 
-fn count_gear_ratios(input: &mut Matrix) -> u32 {
-    let mut result = 0;
-    for row in 0..input.len() {
-        for col in 0..input[row].len() {
-            if input[row][col] == '*' && count_adjacent(input, row, col) == 2 {
-                let mut gear_parts: Vec<u32> = Vec::new();
-                gear_parts.append(&mut check_adjascent_and_diagonals(&mut input[row - 1], col));
-                gear_parts.append(&mut check_adjascent_and_diagonals(&mut input[row], col));
-                gear_parts.append(&mut check_adjascent_and_diagonals(&mut input[row + 1], col));
-                if gear_parts.len() != 2 {
-                    panic!("Gear Parts should be 2");
-                }
-                result += gear_parts.iter().product::<u32>();
-            }
-        }
+    /// The product of the two numbers next to each `*` that touches exactly two.
+    fn gear_ratios(&self) -> impl Iterator<Item = u32> + '_ {
+        self.symbols
+            .iter()
+            .zip(&self.adjacent)
+            .filter(|(symbol, numbers)| symbol.kind == '*' && numbers.len() == 2)
+            .map(|(_, numbers)| numbers.iter().map(|i| self.numbers[*i].value).product())
     }
-    result
 }
 
 #[cfg(test)]
@@ -143,4 +128,41 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(467835));
     }
+
+    #[test]
+    fn test_schematic() {
+        let schematic = Schematic::parse(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(
+            schematic.numbers[0],
+            Number {
+                value: 467,
+                row: 0,
+                cols: 0..3,
+            }
+        );
+        assert_eq!(
+            schematic.symbols[0],
+            Symbol {
+                kind: '*',
+                row: 1,
+                col: 3,
+            }
+        );
+        let values = |numbers: Vec<&Number>| numbers.iter().map(|n| n.value).collect_vec();
+        assert_eq!(
+            values(schematic.numbers_touching(|kind| kind == '#')),
+            vec![633]
+        );
+        assert_eq!(
+            values(schematic.numbers_touching(|kind| kind == '+')),
+            vec![592]
+        );
+        assert_eq!(
+            values(schematic.numbers_touching(|kind| kind == '*')),
+            vec![467, 35, 617, 755, 598]
+        );
+
+        // touching two symbols still makes just one part number
+        assert_eq!(part_one(".*.\n.5.\n..#\n"), Some(5));
+    }
 }
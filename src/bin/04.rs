@@ -1,114 +1,67 @@
-use std::collections::{HashMap, HashSet};
-
-use regex::Regex;
+use advent_of_code::helpers::parse::ints;
 
 advent_of_code::solution!(4);
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let cards = parse_input(input);
+    let matches = parse_input(input)?;
 
-    let part1: u32 = cards.iter().map(calculate_card_points).sum();
+    let part1: u32 = matches
+        .iter()
+        .map(|count| calculate_card_points(*count))
+        .sum();
     Some(part1)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let cards = parse_input(input);
+    let matches = parse_input(input)?;
+
+    Some(count_scratch_cards(&matches))
+}
+
+/// Card numbers are all under 100, so a set of them fits in one `u128` bit each.
+type NumberSet = u128;
 
-    let mut counter: u32 = 0;
-    let mut memo: HashMap<usize, u32> = HashMap::new();
-    (0..cards.len()).for_each(|i| win_scratch_cards(&cards, i, &mut counter, &mut memo));
-    Some(counter)
+fn number_set(numbers: &str) -> Option<NumberSet> {
+    ints::<u32>(numbers).try_fold(0, |set, number| Some(set | 1u128.checked_shl(number)?))
 }
 
-fn parse_input(input: &str) -> Vec<Card> {
-    let re = Regex::new(
-        r"Card\s+(?P<card_id>\d+): (?P<winning_numbers>[\d ]+) \| (?P<numbers_you_have>[\d ]+)$",
-    )
-    .unwrap();
+/// How many of your numbers each card wins with, in card order.
+fn parse_input(input: &str) -> Option<Vec<u32>> {
     input
         .lines()
-        .map(|l| {
-            let caps = re.captures(l).unwrap();
-            let winning_numbers: HashSet<u32> = caps["winning_numbers"]
-                .split_whitespace()
-                .map(|ele| ele.parse().unwrap())
-                .collect();
-            let numbers_you_have: Vec<u32> = caps["numbers_you_have"]
-                .split_whitespace()
-                .map(|ele| ele.parse().unwrap())
-                .collect();
-
-            Card {
-                _id: caps["card_id"].parse().unwrap(),
-                winning_numbers,
-                numbers_you_have,
-            }
+        .map(|line| {
+            let (_, numbers) = line.split_once(':')?;
+            let (winning_numbers, numbers_you_have) = numbers.split_once('|')?;
+            let matches = number_set(winning_numbers)? & number_set(numbers_you_have)?;
+            Some(matches.count_ones())
         })
         .collect()
 }
 
-fn count_winning_numbers(input: &Card) -> u32 {
-    input
-        .numbers_you_have
-        .iter()
-        .filter(|n| input.winning_numbers.contains(n))
-        .count() as u32
-}
-
-fn calculate_card_points(input: &Card) -> u32 {
-    match count_winning_numbers(input) {
+fn calculate_card_points(matches: u32) -> u32 {
+    match matches {
         0 => 0,
         count => 2_u32.pow(count - 1),
     }
 }
 
-// TODO: I desperately need to keep a context of this recursion
-fn _old_win_scratch_cards(cards: &Vec<Card>, current_card_index: usize, counter: &mut u32) {
-    *counter += 1;
-    let winning_numbers = count_winning_numbers(&cards[current_card_index]);
-    (current_card_index + 1..current_card_index + 1 + winning_numbers as usize)
-        .for_each(|i| _old_win_scratch_cards(cards, i, counter));
-}
-
-// DONE!
-// benchmarks show an improvement from 30.16s to 1.378ms :D
-// almost 22thousand times faster
-fn win_scratch_cards(
-    cards: &Vec<Card>,
-    current_card_index: usize,
-    counter: &mut u32,
-    memo: &mut HashMap<usize, u32>,
-) {
-    *counter += 1;
-
-    if let Some(&cached_result) = memo.get(&current_card_index) {
-        // If result is already memoized, use the cached value and return
-        *counter += cached_result;
-        return;
+/// Cards only ever win copies of later cards, so by the time a card is reached every copy of
+/// it is known, and each of them wins one more copy of the next `matches` cards.
+fn count_scratch_cards(matches: &[u32]) -> u32 {
+    let mut copies = vec![1; matches.len()];
+    for (card, count) in matches.iter().enumerate() {
+        let won = card + 1..(card + 1 + *count as usize).min(matches.len());
+        for next in won {
+            copies[next] += copies[card];
+        }
     }
-
-    let winning_numbers = count_winning_numbers(&cards[current_card_index]);
-    let mut subcounter = 0;
-
-    (current_card_index + 1..current_card_index + 1 + winning_numbers as usize).for_each(|i| {
-        win_scratch_cards(cards, i, &mut subcounter, memo);
-    });
-
-    // Save the result in the memoization map
-    memo.insert(current_card_index, subcounter);
-    *counter += subcounter;
-}
-
-#[derive(Debug)]
-struct Card {
-    _id: u32,
-    winning_numbers: HashSet<u32>,
-    numbers_you_have: Vec<u32>,
+    copies.iter().sum()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::rng::XorShift;
 
     #[test]
     fn test_part_one() {
@@ -121,4 +74,33 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(30));
     }
+
+    // the original solution, scratching every single copy
+    fn win_scratch_cards(matches: &[u32], current_card_index: usize, counter: &mut u32) {
+        *counter += 1;
+        let winning_numbers = matches[current_card_index];
+        (current_card_index + 1..current_card_index + 1 + winning_numbers as usize)
+            .for_each(|i| win_scratch_cards(matches, i, counter));
+    }
+
+    #[test]
+    fn test_against_recursion() {
+        assert_eq!(
+            parse_input("Card 1: 99  1 | 1 98 99\nCard 2: 5 | 6\n"),
+            Some(vec![2, 0])
+        );
+        assert_eq!(parse_input("Card 1: 128 | 128\n"), None);
+
+        let mut rng = XorShift::new(7);
+        for cards in 1..=12 {
+            let matches = (0..cards)
+                // cards never win copies past the end of the table
+                .map(|card| rng.below((cards - card) as u64) as u32)
+                .collect::<Vec<_>>();
+
+            let mut counter = 0;
+            (0..matches.len()).for_each(|i| win_scratch_cards(&matches, i, &mut counter));
+            assert_eq!(count_scratch_cards(&matches), counter, "{matches:?}");
+        }
+    }
 }
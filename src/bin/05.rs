@@ -1,88 +1,90 @@
-use rayon::prelude::*;
-use std::collections::HashMap;
-
-use regex::{Captures, Regex};
+use advent_of_code::helpers::parse::{ints, sections};
 
 advent_of_code::solution!(5);
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let re = Regex::new(
-        r"seeds: (?P<seeds>[\d ]+)\s+seed-to-soil map: (?P<seed_to_soil>[\d ]+)\s+soil-to-fertilizer map: (?P<soil_to_fertilizer>[\d ]+)\s+fertilizer-to-water map: (?P<fertilizer_to_water>[\d ]+)\s+water-to-light map: (?P<water_to_light>[\d ]+)\s+light-to-temperature map: (?P<light_to_temperature>[\d ]+)\s+temperature-to-humidity map: (?P<temperature_to_humidity>[\d ]+)\s+humidity-to-location map: (?P<humidity_to_location>[\d ]+)$"
-    )
-    .unwrap();
-    // let input: &str = &INPUT.lines().collect::<Vec<&str>>().join(" ");
-    let input = input.lines().collect::<Vec<&str>>().join(" ");
-
-    let caps = re.captures(&input).unwrap();
-    let seeds: Vec<u32> = caps["seeds"]
-        .split_whitespace()
-        .map(|e| e.parse().unwrap())
-        .collect();
-    let mappings = parse_all_mappings(&caps);
-    seeds
-        .par_iter()
-        .map(|seed| from_seed_to_location(*seed, &mappings))
-        .min()
+pub fn part_one(input: &str) -> Option<u64> {
+    let (seeds, maps) = parse(input)?;
+    let ranges = seeds.iter().map(|seed| (*seed, seed + 1)).collect();
+    lowest_location(ranges, &maps)
 }
 
-// I loved this solution so I'll keep it here
-pub fn part_two(input: &str) -> Option<usize> {
+pub fn part_two(input: &str) -> Option<u64> {
     let (seeds, maps) = parse(input)?;
-
-    let ranges: Vec<(usize, usize)> = seeds
-        .chunks(2)
-        .map(|vals| {
-            let x = vals[0] - 1;
-            let y = x + vals[1];
-            (x, y)
-        })
+    let ranges = seeds
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[0] + pair[1]))
+        .filter(|(start, end)| start < end)
         .collect();
+    lowest_location(ranges, &maps)
+}
 
-    // Go backwards, from location to seed, and check if each number if a possible the solution.
-    // The first one we find is the lowest possible solution. Luckily for our inputs the solution
-    // will be in the millions, so it finishes quite fast
-    for i in 0..usize::MAX {
-        let start_value = maps.iter().rev().fold(i, |acc, map| {
-            let range = map.iter().find(|range| {
-                acc >= range.destination_start && acc < (range.destination_start + range.length)
-            });
-
-            match range {
-                Some(range) => acc + range.source_start - range.destination_start,
-                None => acc,
-            }
-        });
+/// One line of a map: `length` values starting at `source_start` are sent to the values
+/// starting at `destination_start`.
+struct Range {
+    length: u64,
+    source_start: u64,
+    destination_start: u64,
+}
 
-        if ranges
-            .iter()
-            .any(|r| start_value >= r.0 && start_value <= r.1)
-        {
-            return Some(i);
-        }
-    }
+/// Half-open `[start, end)` intervals of values.
+type Interval = (u64, u64);
 
-    None
+/// Pushes every interval through each map in turn, splitting intervals wherever they straddle
+/// a range boundary, and returns the lowest value that comes out the other end.
+fn lowest_location(mut intervals: Vec<Interval>, maps: &[Vec<Range>]) -> Option<u64> {
+    for map in maps {
+        intervals = apply_map(intervals, map);
+    }
+    intervals
+        .iter()
+        .filter(|(start, end)| start < end)
+        .map(|(start, _)| *start)
+        .min()
 }
 
-struct Range {
-    length: usize,
-    source_start: usize,
-    destination_start: usize,
+fn apply_map(intervals: Vec<Interval>, map: &[Range]) -> Vec<Interval> {
+    let mut mapped = Vec::new();
+    let mut pending = intervals;
+
+    for range in map {
+        let source_end = range.source_start + range.length;
+        let mut unmatched = Vec::new();
+        for (start, end) in pending {
+            let overlap_start = start.max(range.source_start);
+            let overlap_end = end.min(source_end);
+            if overlap_start >= overlap_end {
+                unmatched.push((start, end));
+                continue;
+            }
+            mapped.push((
+                overlap_start - range.source_start + range.destination_start,
+                overlap_end - range.source_start + range.destination_start,
+            ));
+            if start < overlap_start {
+                unmatched.push((start, overlap_start));
+            }
+            if overlap_end < end {
+                unmatched.push((overlap_end, end));
+            }
+        }
+        pending = unmatched;
+    }
+
+    // anything no range claimed keeps its value
+    mapped.extend(pending);
+    mapped
 }
 
 fn parse_map(chunk: &str) -> Option<Vec<Range>> {
-    let ranges: Vec<Range> = chunk
-        .lines()
-        .filter(|l| l.chars().next().is_some_and(|c| c.is_ascii_digit()))
-        .map(|l| {
-            let mut splits = l.split_whitespace().filter_map(|x| x.parse::<usize>().ok());
-            Range {
-                destination_start: splits.next().unwrap(),
-                source_start: splits.next().unwrap(),
-                length: splits.next().unwrap(),
-            }
+    let values = ints::<u64>(chunk).collect::<Vec<_>>();
+    let ranges = values
+        .chunks_exact(3)
+        .map(|range| Range {
+            destination_start: range[0],
+            source_start: range[1],
+            length: range[2],
         })
-        .collect();
+        .collect::<Vec<_>>();
 
     if ranges.is_empty() {
         None
@@ -91,68 +93,14 @@ fn parse_map(chunk: &str) -> Option<Vec<Range>> {
     }
 }
 
-fn parse(input: &str) -> Option<(Vec<usize>, Vec<Vec<Range>>)> {
-    let seeds = input
-        .lines()
-        .next()?
-        .split_once(':')?
-        .1
-        .split_ascii_whitespace()
-        .filter_map(|x| x.parse().ok())
-        .collect();
-
-    let maps = input.split("\n\n").skip(1).filter_map(parse_map).collect();
+fn parse(input: &str) -> Option<(Vec<u64>, Vec<Vec<Range>>)> {
+    let mut sections = sections(input);
+    let seeds = ints(sections.next()?).collect();
+    let maps = sections.map(parse_map).collect::<Option<_>>()?;
 
     Some((seeds, maps))
 }
 
-const CAPTURES_NAMES: [&str; 7] = [
-    "seed_to_soil",
-    "soil_to_fertilizer",
-    "fertilizer_to_water",
-    "water_to_light",
-    "light_to_temperature",
-    "temperature_to_humidity",
-    "humidity_to_location",
-];
-
-#[derive(Debug)]
-struct Mapping(u32, u32, u32);
-
-fn captures_to_mappings(caps: &Captures, capture_name: &str) -> Vec<Mapping> {
-    caps[capture_name]
-        .split_whitespace()
-        .map(|e| e.parse().unwrap())
-        .collect::<Vec<u32>>()
-        .chunks(3)
-        .map(|chunk| Mapping(chunk[0], chunk[1], chunk[2]))
-        .collect()
-}
-
-fn parse_all_mappings(caps: &Captures) -> HashMap<String, Vec<Mapping>> {
-    CAPTURES_NAMES
-        .iter()
-        .fold(HashMap::new(), |mut mappings, capture_name| {
-            let current_mapping = captures_to_mappings(caps, capture_name);
-            mappings.insert(capture_name.to_string(), current_mapping);
-            mappings
-        })
-}
-
-fn from_seed_to_location(seed: u32, mappings: &HashMap<String, Vec<Mapping>>) -> u32 {
-    CAPTURES_NAMES.iter().fold(seed, |current, capture_name| {
-        match mappings
-            .get(*capture_name)
-            .unwrap()
-            .iter()
-            .find(|Mapping(_, source, range)| current >= *source && current < source + range)
-        {
-            Some(Mapping(destination, source, _)) => current - source + destination,
-            _ => current,
-        }
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,4 +116,13 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(46));
     }
+
+    #[test]
+    fn test_empty_ranges() {
+        // the first seed range holds no seeds, so its low start must not count
+        let input = "seeds: 1 0 50 2\n\nseed-to-soil map:\n60 50 2\n";
+        assert_eq!(part_two(input), Some(60));
+        assert_eq!(lowest_location(vec![(3, 3), (9, 10)], &[]), Some(9));
+        assert_eq!(lowest_location(vec![(3, 3)], &[]), None);
+    }
 }
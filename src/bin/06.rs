@@ -1,28 +1,32 @@
+use advent_of_code::helpers::math::isqrt;
+
 advent_of_code::solution!(6);
 
-pub fn part_one(input: &str) -> Option<f64> {
+pub fn part_one(input: &str) -> Option<u64> {
+    let races = parse(input, false)?;
     Some(
-        parse(input, false)
+        races
             .iter()
-            .map(count_winning_conditions)
+            .map(|(time, distance)| count_winning_conditions(*time, *distance))
             .product(),
     )
 }
 
-pub fn part_two(input: &str) -> Option<f64> {
+pub fn part_two(input: &str) -> Option<u64> {
+    let races = parse(input, true)?;
     Some(
-        parse(input, true)
+        races
             .iter()
-            .map(count_winning_conditions)
+            .map(|(time, distance)| count_winning_conditions(*time, *distance))
             .product(),
     )
 }
 
-fn parse(input: &str, squash_numbers: bool) -> Vec<(i64, i64)> {
-    let results: Vec<Vec<i64>> = input
+fn parse(input: &str, squash_numbers: bool) -> Option<Vec<(u64, u64)>> {
+    let results = input
         .lines()
         .map(|l| {
-            let line = l.split_once(": ").unwrap().1;
+            let line = l.split_once(": ")?.1;
             let numbers = if squash_numbers {
                 [line.replace(' ', "")].to_vec()
             } else {
@@ -33,25 +37,46 @@ fn parse(input: &str, squash_numbers: bool) -> Vec<(i64, i64)> {
 
             numbers
                 .iter()
-                .filter_map(|n| n.parse::<i64>().ok())
-                .collect::<Vec<i64>>()
+                .map(|n| n.parse::<u64>().ok())
+                .collect::<Option<Vec<u64>>>()
         })
-        .collect();
+        .collect::<Option<Vec<_>>>()?;
 
-    results[0]
-        .iter()
-        .zip(results[1].iter())
-        .map(|(t, d)| (*t, *d))
-        .collect::<Vec<(i64, i64)>>()
+    let [times, distances] = results.as_slice() else {
+        return None;
+    };
+    Some(
+        times
+            .iter()
+            .copied()
+            .zip(distances.iter().copied())
+            .collect(),
+    )
 }
 
-fn count_winning_conditions((t, d): &(i64, i64)) -> f64 {
-    // I want the smallest number X that satisfies
-    // X * (T - X) > D -> -x2 + xt - d > 0
-    let delta = (t.pow(2) - 4 * d) as f64;
-    let x1 = ((-1 * t) as f64 + f64::sqrt(delta)) / -2f64;
-    let x2 = ((-1 * t) as f64 - f64::sqrt(delta)) / -2f64;
-    (x2 - 1.0).ceil() - (x1 + 1.0).floor() + 1.0
+/// Holding the button for `x` ms wins when `x * (time - x) > distance`. The winning holds are
+/// a range symmetric around `time / 2`, so only its lower end has to be found.
+fn count_winning_conditions(time: u64, distance: u64) -> u64 {
+    let wins = |hold: u64| hold as u128 * (time - hold) as u128 > distance as u128;
+
+    // the lower root of x^2 - time * x + distance = 0, give or take one
+    let discriminant = (time as u128 * time as u128).checked_sub(4 * distance as u128);
+    let Some(discriminant) = discriminant else {
+        return 0;
+    };
+    // the root is at most `time`, so it fits back into a u64
+    let mut lowest = (time - isqrt(discriminant) as u64) / 2;
+    while lowest <= time / 2 && !wins(lowest) {
+        lowest += 1;
+    }
+    while lowest > 0 && wins(lowest - 1) {
+        lowest -= 1;
+    }
+
+    if lowest > time / 2 {
+        return 0;
+    }
+    time - 2 * lowest + 1
 }
 
 #[cfg(test)]
@@ -61,12 +86,43 @@ mod tests {
     #[test]
     fn test_part_one() {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(288.0));
+        assert_eq!(result, Some(288));
     }
 
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(71503.0));
+        assert_eq!(result, Some(71503));
+    }
+
+    #[test]
+    fn test_edge_cases() {
+        // holding 10 or 20 ms exactly matches the record, which isn't a win
+        assert_eq!(count_winning_conditions(30, 200), 9);
+        // the best possible hold only ties the record
+        assert_eq!(count_winning_conditions(4, 4), 0);
+        // races too short to move at all, or to reach the record
+        assert_eq!(count_winning_conditions(0, 0), 0);
+        assert_eq!(count_winning_conditions(1, 0), 0);
+        assert_eq!(count_winning_conditions(2, 5), 0);
+        // large enough that f64 can't tell the products apart
+        let time = 3_000_000_001;
+        let distance = 1500000000 * 1500000001 - 1;
+        assert_eq!(count_winning_conditions(time, distance), 2);
+        // times past 2^32 have discriminants that don't fit in a u64
+        let time = 6_000_000_000;
+        assert_eq!(count_winning_conditions(time, 0), time - 1);
+        assert_eq!(
+            count_winning_conditions(time, 3_000_000_000u64.pow(2) - 2),
+            3
+        );
+        assert_eq!(count_winning_conditions(u64::MAX, 0), u64::MAX - 1);
+
+        for time in 0..40 {
+            for distance in 0..=time * time / 4 + 1 {
+                let brute_force = (0..=time).filter(|x| x * (time - x) > distance).count();
+                assert_eq!(count_winning_conditions(time, distance), brute_force as u64);
+            }
+        }
     }
 }
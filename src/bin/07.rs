@@ -1,71 +1,16 @@
-use core::cmp::Ordering;
-use std::collections::HashMap;
-use std::collections::HashSet;
+use itertools::Itertools;
 
 advent_of_code::solution!(7);
 
 pub fn part_one(input: &str) -> Option<u32> {
-    const CARD_ORDER: [char; 13] = [
-        '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
-    ];
-    let hands: Vec<(&str, u32)> = input
-        .lines()
-        .map(|line| {
-            let tokens: Vec<&str> = line.split_whitespace().collect();
-            (tokens[0], tokens[1].parse::<u32>().unwrap())
-        })
-        .collect();
-
-    let mut hands_with_score: Vec<(&str, u32, Score)> = hands
-        .clone()
-        .iter()
-        .map(|hand| {
-            let score = calculate_hand_score(hand.0, false);
-            (hand.0, hand.1, score)
-        })
-        .collect();
-
-    hands_with_score.sort_by(|a, b| compare_hands(a, b, CARD_ORDER.to_vec()));
-    let total_winnings: u32 = hands_with_score
-        .iter()
-        .enumerate()
-        .map(|(i, (_, bid, _))| bid * (i as u32 + 1))
-        .sum();
-    Some(total_winnings)
+    total_winnings(input, &Rules::STANDARD)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    const CARD_ORDER: [char; 13] = [
-        'J', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'Q', 'K', 'A',
-    ];
-    let hands: Vec<(&str, u32)> = input
-        .lines()
-        .map(|line| {
-            let tokens: Vec<&str> = line.split_whitespace().collect();
-            (tokens[0], tokens[1].parse::<u32>().unwrap())
-        })
-        .collect();
-
-    let mut hands_with_score: Vec<(&str, u32, Score)> = hands
-        .clone()
-        .iter()
-        .map(|hand| {
-            let jokerized_hand = convert_jokers(hand.0);
-            let score = calculate_hand_score(&jokerized_hand, true);
-            (hand.0, hand.1, score)
-        })
-        .collect();
-
-    hands_with_score.sort_by(|a, b| compare_hands(a, b, CARD_ORDER.to_vec()));
-    let total_winnings: u32 = hands_with_score
-        .iter()
-        .enumerate()
-        .map(|(i, (_, bid, _))| bid * (i as u32 + 1))
-        .sum();
-    Some(total_winnings)
+    total_winnings(input, &Rules::JOKERS)
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum Score {
     HighCard = 0,
     OnePair,
@@ -76,90 +21,104 @@ enum Score {
     FiveOfAKind,
 }
 
-fn highest_card_frequency(hand: &str, filter_jokers: bool) -> (char, i32) {
-    if hand == "JJJJJ" {
-        return ('J', 5);
+/// Cards in a hand.
+const HAND_SIZE: usize = 5;
+/// Distinct cards a ranking can hold, so each card's strength fits in 5 bits of a key.
+const MAX_CARDS: usize = 32;
+
+/// A variant of Camel Cards.
+struct Rules<'a> {
+    /// Every card, weakest first. At most `MAX_CARDS` of them.
+    ranking: &'a str,
+    /// Cards that count as whatever card makes the hand strongest.
+    wildcards: &'a str,
+    /// Scores a hand from how often it holds each card, indexed by strength, and how many
+    /// wildcards it holds.
+    evaluate: fn(&[u8; MAX_CARDS], u8) -> Score,
+}
+
+impl Rules<'static> {
+    const STANDARD: Self = Self {
+        ranking: "23456789TJQKA",
+        wildcards: "",
+        evaluate: of_a_kind,
+    };
+    const JOKERS: Self = Self {
+        ranking: "J23456789TQKA",
+        wildcards: "J",
+        evaluate: of_a_kind,
+    };
+}
+
+/// The usual hand types, which only look at the largest groups of equal cards.
+fn of_a_kind(counts: &[u8; MAX_CARDS], wild: u8) -> Score {
+    let mut counts = *counts;
+    // wildcards always do best joining the most common card
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    match (counts[0] + wild, counts[1]) {
+        (5.., _) => Score::FiveOfAKind,
+        (4, _) => Score::FourOfAKind,
+        (3, 2) => Score::FullHouse,
+        (3, _) => Score::ThreeOfAKind,
+        (2, 2) => Score::TwoPair,
+        (2, _) => Score::OnePair,
+        _ => Score::HighCard,
     }
-    hand.chars()
-        .fold(HashMap::new(), |mut acc, cur_card| {
-            let count = match acc.get(&cur_card) {
-                Some(count) => *count,
-                _ => 0,
-            };
-            acc.insert(cur_card, count + 1);
-            acc
-        })
-        .iter()
-        .filter(|entry| !filter_jokers || entry.0 != &'J')
-        .max_by_key(|entry| entry.1)
-        .map(|(k, v)| (*k, *v))
-        .unwrap()
 }
 
-fn calculate_hand_score(hand: &str, filter_jokers: bool) -> Score {
-    let unique_cards: HashSet<char> = hand.chars().collect();
-    match unique_cards.len() {
-        1 => Score::FiveOfAKind,
-        2 => {
-            // could be Score::FourOfAKind or Score::FullHouse
-            // Check the Highest Frequency of a card.
-            // if it's 1 or 4, then we have FourOfAKind
-            // otherwise, it's FullHouse
-            match highest_card_frequency(hand, filter_jokers) {
-                (_, 4) => Score::FourOfAKind,
-                _ => Score::FullHouse,
-            }
+impl Rules<'_> {
+    fn strength(&self, card: u8) -> Option<u32> {
+        if self.ranking.len() > MAX_CARDS {
+            return None;
         }
-        3 => {
-            // could be three of a kind or two pair
-            // Check the Highest Frequency of a card
-            // if it's 3, then we have ThreeOfAKind
-            // otherwise, it's TwoPairs
-            match highest_card_frequency(hand, filter_jokers) {
-                (_, 3) => Score::ThreeOfAKind,
-                _ => Score::TwoPair,
+        self.ranking
+            .bytes()
+            .position(|c| c == card)
+            .map(|s| s as u32)
+    }
+
+    fn score(&self, hand: &str) -> Option<Score> {
+        let mut counts = [0u8; MAX_CARDS];
+        let mut wild = 0;
+        for card in hand.bytes() {
+            if self.wildcards.as_bytes().contains(&card) {
+                wild += 1;
+            } else {
+                counts[self.strength(card)? as usize] += 1;
             }
         }
-        4 => Score::OnePair,
-        _ => Score::HighCard,
+        Some((self.evaluate)(&counts, wild))
     }
-}
 
-fn compare_hands(
-    a: &(&str, u32, Score),
-    b: &(&str, u32, Score),
-    card_order: Vec<char>,
-) -> Ordering {
-    // Ordering::Equal
-    match (a.2 as u32).cmp(&(b.2 as u32)) {
-        Ordering::Equal => {
-            for (ai, bi) in a.0.chars().zip(b.0.chars()) {
-                let left = card_order
-                    .iter()
-                    .position(|c| c.eq_ignore_ascii_case(&ai))
-                    .unwrap();
-                let right = card_order
-                    .iter()
-                    .position(|c| c.eq_ignore_ascii_case(&bi))
-                    .unwrap();
-                if left != right {
-                    return left.cmp(&right);
-                }
-            }
-            Ordering::Equal
+    /// Packs the hand's score and then each card's strength into an integer, so comparing
+    /// keys compares hands. Only hands of `HAND_SIZE` cards fit.
+    fn key(&self, hand: &str) -> Option<u32> {
+        if hand.len() != HAND_SIZE {
+            return None;
         }
-        ordering => ordering,
+        hand.bytes()
+            .try_fold(self.score(hand)? as u32, |key, card| {
+                Some(key << 5 | self.strength(card)?)
+            })
     }
 }
 
-fn convert_jokers(hand: &str) -> String {
-    if !hand.chars().any(|c| c == 'J') {
-        // println!("No jokers found: {}", hand);
-        return hand.to_string();
-    }
+fn total_winnings(input: &str, rules: &Rules) -> Option<u32> {
+    let hands = input
+        .lines()
+        .map(|line| {
+            let (hand, bid) = line.split_once(' ')?;
+            Some((rules.key(hand)?, bid.parse::<u32>().ok()?))
+        })
+        .collect::<Option<Vec<_>>>()?;
 
-    let (most_frequent, _) = highest_card_frequency(hand, true);
-    hand.replace('J', &most_frequent.to_string())
+    let total_winnings = hands
+        .iter()
+        .sorted_unstable()
+        .enumerate()
+        .map(|(i, (_, bid))| bid * (i as u32 + 1))
+        .sum();
+    Some(total_winnings)
 }
 
 #[cfg(test)]
@@ -177,4 +136,73 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(5905));
     }
+
+    #[test]
+    fn test_variant_rules() {
+        let rules = Rules {
+            ranking: "QJ23456789TKA",
+            wildcards: "JQ",
+            evaluate: of_a_kind,
+        };
+        let standard = Rules {
+            ranking: rules.ranking,
+            wildcards: "",
+            evaluate: of_a_kind,
+        };
+        // the best hand any substitution of the wildcards can make
+        let substituted = |hand: &str| {
+            let options = hand.chars().map(|card| match card {
+                'J' | 'Q' => "23456789TKA".chars().collect_vec(),
+                _ => vec![card],
+            });
+            options
+                .multi_cartesian_product()
+                .map(|cards| standard.score(&cards.iter().collect::<String>()))
+                .max()
+                .flatten()
+        };
+        for hand in [
+            "JQJQJ", "QQ234", "JQ234", "J2233", "JQ223", "JQQ2A", "KTJQT", "AAAJ2",
+        ] {
+            assert_eq!(rules.score(hand), substituted(hand), "{hand}");
+        }
+
+        // wildcards are the weakest cards when breaking ties
+        assert!(rules.key("Q2222") < rules.key("J2222"));
+        assert!(rules.key("J2222") < rules.key("22222"));
+
+        // twos beat aces when the ranking is flipped
+        let reversed = Rules {
+            ranking: "AKQJT98765432",
+            wildcards: "",
+            evaluate: of_a_kind,
+        };
+        assert!(reversed.key("AAAAK") < reversed.key("22223"));
+        assert_eq!(reversed.key("22X22"), None);
+        assert_eq!(reversed.key("222222"), None);
+        assert_eq!(reversed.key("2222"), None);
+
+        // a run of five cards counts as a full house
+        let straights = Rules {
+            ranking: Rules::STANDARD.ranking,
+            wildcards: "",
+            evaluate: |counts, wild| {
+                if wild == 0 && counts.windows(5).any(|run| run.iter().all(|c| *c == 1)) {
+                    Score::FullHouse
+                } else {
+                    of_a_kind(counts, wild)
+                }
+            },
+        };
+        assert_eq!(straights.score("65432"), Some(Score::FullHouse));
+        assert!(straights.key("AAAK2") < straights.key("65432"));
+        assert!(straights.key("65432") < straights.key("AAAKK"));
+
+        let too_many_cards = Rules {
+            ranking: "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789",
+            wildcards: "",
+            evaluate: of_a_kind,
+        };
+        assert_eq!(too_many_cards.score("AAAAA"), None);
+    }
 }
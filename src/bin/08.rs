@@ -1,71 +1,89 @@
-use advent_of_code::helpers::math::lowest_common_multiple;
+use advent_of_code::helpers::{
+    cycle::{find_cycle, first_common_hit, Hits},
+    hash::FastMap,
+};
 use itertools::Itertools;
-use std::collections::HashMap;
 
 advent_of_code::solution!(8);
 
 pub fn part_one(input: &str) -> Option<usize> {
-    let (instructions, maps) = parse_input(input)?;
-
-    let mut current_step = "AAA";
-    for counter in 0..usize::MAX {
-        let current_instruction = instructions.chars().nth(counter % instructions.len());
-        current_step = match current_instruction {
-            Some('L') => &maps.get(current_step).unwrap().0,
-            Some('R') => &maps.get(current_step).unwrap().1,
-            _ => unreachable!(),
-        };
-        if current_step == "ZZZ" {
-            return Some(counter + 1);
-        }
-    }
-    None
+    let network = Network::parse(input)?;
+    let start = network.id("AAA")?;
+    let target = network.id("ZZZ")?;
+
+    let ghost = network.walk(start, |node| node == target);
+    first_common_hit(&[ghost], 1)
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    let (instructions, maps) = parse_input(input)?;
-
-    let initial_steps = maps.keys().filter(|step| step.ends_with('A')).collect_vec();
-    let mut totals: Vec<usize> = Vec::new();
-    for step in initial_steps {
-        let mut current_step = step;
-        for counter in 0..usize::MAX {
-            let current_instruction = instructions.chars().nth(counter % instructions.len());
-            current_step = match current_instruction {
-                Some('L') => &maps.get(current_step).unwrap().0,
-                Some('R') => &maps.get(current_step).unwrap().1,
-                _ => unreachable!(),
-            };
-            if current_step.ends_with('Z') {
-                totals.push(counter + 1);
-                break;
-            }
-        }
-    }
+    let network = Network::parse(input)?;
+
+    let ghosts = (0..network.names.len())
+        .filter(|node| network.names[*node].ends_with('A'))
+        .map(|start| network.walk(start, |node| network.names[node].ends_with('Z')))
+        .collect_vec();
 
-    Some(lowest_common_multiple(&totals))
+    first_common_hit(&ghosts, 1)
 }
 
-type Mappings = HashMap<String, (String, String)>;
-fn parse_input(input: &str) -> Option<(&str, Mappings)> {
-    let (instructions, maps) = input.split_once("\n\n")?;
-    let parsed_maps: Mappings = maps.split('\n').fold(HashMap::new(), |mut acc, line| {
-        match line.split_once(" = ") {
-            Some((source, options)) => {
-                let parsed_options = options
-                    .split(", ")
-                    .map(|opt| opt.chars().filter(|c| c.is_alphanumeric()).collect())
-                    .collect_tuple()
-                    .unwrap();
-
-                acc.insert(source.to_string(), parsed_options);
-                acc
-            }
-            _ => acc,
-        }
-    });
-
-    Some((instructions, parsed_maps))
+/// The desert map with node names interned to their index in `names`.
+struct Network<'a> {
+    instructions: &'a [u8],
+    names: Vec<&'a str>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+impl Network<'_> {
+    fn parse(input: &str) -> Option<Network<'_>> {
+        let (instructions, maps) = input.split_once("\n\n")?;
+        let nodes = maps
+            .lines()
+            .map(|line| {
+                let (source, options) = line.split_once(" = ")?;
+                let (left, right) = options
+                    .trim_matches(|c| c == '(' || c == ')')
+                    .split_once(", ")?;
+                Some((source, left, right))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let ids = nodes
+            .iter()
+            .enumerate()
+            .map(|(id, (name, _, _))| (*name, id))
+            .collect::<FastMap<_, _>>();
+        let (left, right) = nodes
+            .iter()
+            .map(|(_, left, right)| Some((*ids.get(left)?, *ids.get(right)?)))
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+
+        Some(Network {
+            instructions: instructions.trim().as_bytes(),
+            names: nodes.iter().map(|(name, _, _)| *name).collect(),
+            left,
+            right,
+        })
+    }
+
+    fn id(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|node| *node == name)
+    }
+
+    /// The steps at which a ghost walking from `start` stands on a target node.
+    fn walk(&self, start: usize, is_target: impl Fn(usize) -> bool) -> Hits {
+        let length = self.instructions.len();
+        let cycle = find_cycle((start, 0), |&(node, instruction)| {
+            let next = match self.instructions[instruction] {
+                b'L' => self.left[node],
+                _ => self.right[node],
+            };
+            (next, (instruction + 1) % length)
+        });
+        cycle.hits(|(node, _)| is_target(*node))
+    }
 }
 
 #[cfg(test)]
@@ -89,4 +107,14 @@ mod tests {
         ));
         assert_eq!(result, Some(6));
     }
+
+    #[test]
+    fn test_unaligned_cycles() {
+        // ghost 1 walks a 3-cycle hitting Z at steps 2, 5, 8..., ghost 2 walks a 2-cycle with
+        // a one step lead-in hitting Z at steps 3, 5, 7..., so first hits alone give lcm(2, 3)
+        let input = "L\n\n\
+            11A = (11B, 11B)\n11B = (11Z, 11Z)\n11Z = (11C, 11C)\n11C = (11B, 11B)\n\
+            22A = (22B, 22B)\n22B = (22C, 22C)\n22C = (22Z, 22Z)\n22Z = (22C, 22C)\n";
+        assert_eq!(part_two(input), Some(5));
+    }
 }
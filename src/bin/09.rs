@@ -1,47 +1,40 @@
-use itertools::Itertools;
+use advent_of_code::helpers::poly::Polynomial;
 
 advent_of_code::solution!(9);
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let problems = parse_input(input);
-
-    let result = problems.iter().map(|problem| predict(problem, true)).sum();
-    Some(result)
+pub fn part_one(input: &str) -> Option<i64> {
+    let histories = parse_input(input);
+    histories.iter().map(|history| predict(history, 1)).sum()
 }
 
-pub fn part_two(input: &str) -> Option<i32> {
-    let problems = parse_input(input);
-    let result = problems.iter().map(|problem| predict(problem, false)).sum();
-    Some(result)
+pub fn part_two(input: &str) -> Option<i64> {
+    let histories = parse_input(input);
+    histories.iter().map(|history| predict(history, -1)).sum()
 }
 
-fn parse_input(input: &str) -> Vec<Vec<i32>> {
+fn parse_input(input: &str) -> Vec<Vec<i64>> {
     input
         .lines()
         .map(|line| {
             line.split_whitespace()
-                .filter_map(|el| el.parse::<i32>().ok())
+                .filter_map(|el| el.parse::<i64>().ok())
                 .collect()
         })
         .collect()
 }
 
-fn predict(line: &[i32], forwards: bool) -> i32 {
-    if line.iter().all(|el| *el == 0) {
-        return 0;
-    }
-
-    let new_line = line
-        .iter()
-        .tuple_windows()
-        .map(|(a, b)| b - a)
-        .collect_vec();
-
-    if forwards {
-        line.last().unwrap() + predict(&new_line, forwards)
+/// The value `steps` after the last reading, or before the first one if `steps` is negative.
+///
+/// The difference pyramid bottoming out at zero means the readings fit a polynomial, so this
+/// evaluates it at the wanted position directly. Returns `None` if the value overflows.
+fn predict(history: &[i64], steps: i64) -> Option<i64> {
+    let x = if steps >= 0 {
+        (history.len() as i64 - 1).checked_add(steps)?
     } else {
-        line.first().unwrap() - predict(&new_line, forwards)
-    }
+        steps
+    };
+    let value = Polynomial::from_samples(history).checked_evaluate(x)?;
+    i64::try_from(value).ok()
 }
 
 #[cfg(test)]
@@ -59,4 +52,53 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(2));
     }
+
+    // the difference pyramid, one step at a time
+    fn pyramid(line: &[i128], forwards: bool) -> i128 {
+        if line.iter().all(|el| *el == 0) {
+            return 0;
+        }
+        let new_line = line.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+        if forwards {
+            line.last().unwrap() + pyramid(&new_line, forwards)
+        } else {
+            line.first().unwrap() - pyramid(&new_line, forwards)
+        }
+    }
+
+    #[test]
+    fn test_against_pyramid() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let mut histories = parse_input(&input);
+        // 21 readings of a degree 6 polynomial, which overflow an i32
+        histories.push(
+            (0..21i64)
+                .map(|x| 3 * x.pow(6) - 7 * x.pow(3) + 11)
+                .collect(),
+        );
+        histories.push((0..8i64).map(|x| -(x * x) + 5 * x).collect());
+        histories.push(vec![42]);
+
+        for history in histories {
+            for forwards in [true, false] {
+                let mut line = history.iter().map(|el| *el as i128).collect::<Vec<_>>();
+                for steps in 1..=5 {
+                    let next = pyramid(&line, forwards);
+                    if forwards {
+                        line.push(next);
+                    } else {
+                        line.insert(0, next);
+                    }
+                    let steps = if forwards { steps } else { -steps };
+                    let expected = i64::try_from(next).ok();
+                    assert_eq!(predict(&history, steps), expected, "{history:?} {steps}");
+                }
+            }
+        }
+
+        // far from the readings the pyramid would take millions of rounds
+        let squares = (0..5i64).map(|x| x * x).collect::<Vec<_>>();
+        assert_eq!(predict(&squares, 1_000_000), Some(1_000_004i64.pow(2)));
+        assert_eq!(predict(&squares, -1_000_000), Some(1_000_000i64.pow(2)));
+    }
 }
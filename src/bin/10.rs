@@ -1,110 +1,177 @@
-use std::collections::HashSet;
-
-use advent_of_code::helpers::matrix::{read_matrix, Matrix};
+use advent_of_code::helpers::polygon::interior_points;
 use itertools::Itertools;
 
 advent_of_code::solution!(10);
 
-type Point = (usize, usize);
-
 pub fn part_one(input: &str) -> Option<usize> {
-    let mut mapping = read_matrix(input);
-    let starting_position = get_start_position(&mapping);
+    let map = PipeMap::parse(input)?;
+    let pipe_loop = map.find_loop()?;
 
-    let mut visited = HashSet::new();
-    navigate_pipes(&mut mapping, starting_position as Point, &mut visited);
+    Some(pipe_loop.len() / 2)
+}
 
-    Some(visited.len() / 2)
+pub fn part_two(input: &str) -> Option<usize> {
+    let map = PipeMap::parse(input)?;
+    let pipe_loop = map.find_loop()?;
+    // `cargo solve 10 --param render=<path>` writes the annotated map to a file
+    if let Some(path) = advent_of_code::template::param::<String>("render") {
+        if let Err(error) = std::fs::write(&path, map.render(&pipe_loop)) {
+            eprintln!("warning: could not write the rendered loop to {path}: {error}");
+        }
+    }
+
+    let vertices = pipe_loop
+        .iter()
+        .map(|tile| ((tile / map.cols) as i64, (tile % map.cols) as i64))
+        .collect_vec();
+    usize::try_from(interior_points(&vertices)).ok()
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut mapping = read_matrix(input);
-    let starting_position = get_start_position(&mapping);
-
-    let mut visited = HashSet::new();
-    navigate_pipes(&mut mapping, starting_position as Point, &mut visited);
-
-    let mut total_count = 0;
-    (0..mapping.len()).for_each(|row| {
-        let mut inside = false;
-        (0..mapping[row].len()).for_each(|col| {
-            let current_cel = mapping[row][col];
-            if current_cel == '!' {
-                inside = !inside;
-            } else if !visited.contains(&(row, col)) && inside {
-                total_count += 1;
-            }
-        });
-    });
+// pipes are stored as the set of directions they connect to
+const NORTH: u8 = 1;
+const EAST: u8 = 2;
+const SOUTH: u8 = 4;
+const WEST: u8 = 8;
+
+const DIRECTIONS: [u8; 4] = [NORTH, EAST, SOUTH, WEST];
 
-    Some(total_count)
+fn opposite(direction: u8) -> u8 {
+    ((direction << 2) | (direction >> 2)) & 0b1111
 }
 
-fn get_start_position(mapping: &Matrix) -> Point {
-    mapping
-        .iter()
-        .enumerate()
-        .filter_map(|(i, row)| {
-            row.iter()
-                .find_position(|c| **c == 'S')
-                .map(|(j, _)| (i, j))
-        })
-        .last()
-        .unwrap()
+fn connections(tile: u8) -> u8 {
+    match tile {
+        b'|' => NORTH | SOUTH,
+        b'-' => EAST | WEST,
+        b'L' => NORTH | EAST,
+        b'J' => NORTH | WEST,
+        b'7' => SOUTH | WEST,
+        b'F' => SOUTH | EAST,
+        _ => 0,
+    }
 }
 
-fn get_next_cell(pipe: char, (row, col): Point, mapping: &Matrix) -> Vec<Point> {
-    match pipe {
-        '|' => vec![(row - 1, col), (row + 1, col)],
-        '-' => vec![(row, col - 1), (row, col + 1)],
-        'L' => vec![(row, col + 1), (row - 1, col)],
-        'J' => vec![(row, col - 1), (row - 1, col)],
-        '7' => vec![(row, col - 1), (row + 1, col)],
-        'F' => vec![(row, col + 1), (row + 1, col)],
-        'S' => {
-            let mut next_cells = Vec::new();
-            if row > 0 && ['|', '7', 'F'].contains(&mapping[row - 1][col]) {
-                next_cells.push((row - 1, col));
-            }
-            if row < mapping.len() - 1 && ['|', 'L', 'J'].contains(&mapping[row + 1][col]) {
-                next_cells.push((row + 1, col));
-            }
-            if col > 0 && ['-', 'L', 'F'].contains(&mapping[row][col - 1]) {
-                next_cells.push((row, col - 1));
-            }
-            if col < mapping[0].len() && ['-', 'J', '7'].contains(&mapping[row][col + 1]) {
-                next_cells.push((row, col + 1));
-            }
-            next_cells
-        }
-        _ => unreachable!(),
+fn pipe(connections: u8) -> u8 {
+    match connections {
+        c if c == NORTH | SOUTH => b'|',
+        c if c == EAST | WEST => b'-',
+        c if c == NORTH | EAST => b'L',
+        c if c == NORTH | WEST => b'J',
+        c if c == SOUTH | WEST => b'7',
+        c if c == SOUTH | EAST => b'F',
+        _ => b'.',
     }
 }
 
-fn navigate_pipes(mapping: &mut Matrix, (row, col): Point, visited: &mut HashSet<Point>) -> bool {
-    let current_cel = mapping[row][col];
-    if current_cel == 'S' && !visited.is_empty() {
-        // prevent shortcutting back to start
-        return visited.len() > 2;
+/// The field of pipes, with the `S` tile replaced by the pipe it really is.
+struct PipeMap {
+    tiles: Vec<u8>,
+    cols: usize,
+    start: usize,
+}
+
+impl PipeMap {
+    fn parse(input: &str) -> Option<Self> {
+        let cols = input.lines().next()?.len();
+        let tiles = input.lines().flat_map(str::bytes).collect_vec();
+        let start = tiles.iter().position(|tile| *tile == b'S')?;
+        let mut map = Self { tiles, cols, start };
+        map.tiles[start] = map.infer_start()?;
+        Some(map)
     }
-    if current_cel == '.' || visited.contains(&(row, col)) {
-        return false;
+
+    fn neighbour(&self, tile: usize, direction: u8) -> Option<usize> {
+        let col = tile % self.cols;
+        let next = match direction {
+            NORTH => tile.checked_sub(self.cols)?,
+            SOUTH => tile + self.cols,
+            WEST if col > 0 => tile - 1,
+            EAST if col + 1 < self.cols => tile + 1,
+            _ => return None,
+        };
+        (next < self.tiles.len()).then_some(next)
     }
 
-    if ['J', 'L', '|'].contains(&current_cel) {
-        mapping[row][col] = '!';
-    } else {
-        mapping[row][col] = '_';
+    /// The start connects to the neighbours whose pipes point back at it. If more than two do,
+    /// the first pair that closes a loop wins.
+    fn infer_start(&mut self) -> Option<u8> {
+        let candidates = DIRECTIONS
+            .into_iter()
+            .filter(|direction| {
+                self.neighbour(self.start, *direction)
+                    .is_some_and(|next| connections(self.tiles[next]) & opposite(*direction) != 0)
+            })
+            .collect_vec();
+
+        candidates.iter().tuple_combinations().find_map(|(a, b)| {
+            self.tiles[self.start] = pipe(a | b);
+            self.find_loop().map(|_| pipe(a | b))
+        })
     }
 
-    visited.insert((row, col));
-    for next in get_next_cell(current_cel, (row, col), mapping).iter() {
-        if navigate_pipes(mapping, *next, visited) {
-            return true;
+    /// Follows the pipes from the start until they lead back to it, returning every tile on
+    /// the way in order. Returns `None` if they lead off the map or into a mismatched pipe.
+    fn find_loop(&self) -> Option<Vec<usize>> {
+        let exits = connections(self.tiles[self.start]);
+        if exits == 0 {
+            return None;
+        }
+        let mut pipe_loop = Vec::new();
+        let mut tile = self.start;
+        // leave through the lowest exit, the loop is the same either way round
+        let mut heading = 1 << exits.trailing_zeros();
+        loop {
+            pipe_loop.push(tile);
+            tile = self.neighbour(tile, heading)?;
+            if tile == self.start {
+                return Some(pipe_loop);
+            }
+            let exits = connections(self.tiles[tile]);
+            if exits & opposite(heading) == 0 {
+                return None;
+            }
+            heading = exits & !opposite(heading);
         }
     }
 
-    false
+    /// Draws the loop in box-drawing characters, the start in heavy ones, and marks every other
+    /// tile `I` or `O` for inside or outside the loop.
+    fn render(&self, pipe_loop: &[usize]) -> String {
+        let mut on_loop = vec![false; self.tiles.len()];
+        pipe_loop.iter().for_each(|tile| on_loop[*tile] = true);
+
+        let mut rendered = String::with_capacity(self.tiles.len() * 3);
+        for (row, tiles) in self.tiles.chunks(self.cols).enumerate() {
+            // crossing a pipe that reaches north flips between outside and inside
+            let mut inside = false;
+            for (col, tile) in tiles.iter().enumerate() {
+                let index = row * self.cols + col;
+                let drawn = match (on_loop[index], index == self.start) {
+                    (false, _) if inside => 'I',
+                    (false, _) => 'O',
+                    (true, is_start) => {
+                        inside ^= connections(*tile) & NORTH != 0;
+                        let (light, heavy) = match tile {
+                            b'|' => ('│', '┃'),
+                            b'-' => ('─', '━'),
+                            b'L' => ('└', '┗'),
+                            b'J' => ('┘', '┛'),
+                            b'7' => ('┐', '┓'),
+                            _ => ('┌', '┏'),
+                        };
+                        if is_start {
+                            heavy
+                        } else {
+                            light
+                        }
+                    }
+                };
+                rendered.push(drawn);
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
 }
 
 #[cfg(test)]
@@ -124,4 +191,27 @@ mod tests {
         ));
         assert_eq!(result, Some(8));
     }
+
+    #[test]
+    fn test_start_shape() {
+        let map = PipeMap::parse(&advent_of_code::template::read_file("examples", DAY)).unwrap();
+        assert_eq!(map.tiles[map.start], b'F');
+
+        // three neighbours point at the start, but only one pair closes a loop
+        let map = PipeMap::parse("FS-\nLJ.\n").unwrap();
+        assert_eq!(map.tiles[map.start], b'7');
+    }
+
+    #[test]
+    fn test_render() {
+        let map = PipeMap::parse(".....\n.S-7.\n.|.|.\n.L-J.\n.....\n").unwrap();
+        let rendered = map.render(&map.find_loop().unwrap());
+        assert_eq!(rendered, "OOOOO\nO┏─┐O\nO│I│O\nO└─┘O\nOOOOO\n");
+
+        let input = advent_of_code::template::read_file_part("examples", DAY, 2);
+        let map = PipeMap::parse(&input).unwrap();
+        let rendered = map.render(&map.find_loop().unwrap());
+        let inside = rendered.chars().filter(|c| *c == 'I').count();
+        assert_eq!(Some(inside), part_two(&input));
+    }
 }
@@ -1,72 +1,51 @@
-use advent_of_code::helpers::matrix::{read_matrix, transpose, Matrix};
-use itertools::Itertools;
-
 advent_of_code::solution!(11);
 
 pub fn part_one(input: &str) -> Option<usize> {
-    static EXPANSION_RATE: usize = 2;
-    let map = read_matrix(input);
-
-    Some(calculate_distances(&map, EXPANSION_RATE))
+    Some(solve(input, 2))
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    static EXPANSION_RATE: usize = if cfg!(test) { 100 } else { 1_000_000 };
-    let map = read_matrix(input);
-
-    Some(calculate_distances(&map, EXPANSION_RATE))
-}
+    // try other rates with `cargo solve 11 --param expansion=<rate>`, part one always uses 2
+    // as the puzzle asks
+    let default_rate = if cfg!(test) { 100 } else { 1_000_000 };
+    let expansion_rate = advent_of_code::template::param("expansion").unwrap_or(default_rate);
 
-fn find_rows_without_galaxies(map: &Matrix) -> Vec<usize> {
-    (0..map.len())
-        .filter(|row| map[*row].iter().all(|c| *c != '#'))
-        .collect_vec()
+    Some(solve(input, expansion_rate))
 }
 
-fn find_columns_without_galaxies(map: &Matrix) -> Vec<usize> {
-    let transposed = transpose(map);
-    find_rows_without_galaxies(&transposed)
-}
+/// Sums the distances between every pair of galaxies once each empty row and column has
+/// grown to `expansion_rate` of them. Manhattan distances split per axis, so each axis is
+/// summed on its own.
+fn solve(input: &str, expansion_rate: usize) -> usize {
+    let cols = input.lines().next().map_or(0, str::len);
+    let mut galaxies_per_row = vec![0; input.lines().count()];
+    let mut galaxies_per_col = vec![0; cols];
+    for (row, line) in input.lines().enumerate() {
+        for (col, _) in line.bytes().enumerate().filter(|(_, c)| *c == b'#') {
+            galaxies_per_row[row] += 1;
+            galaxies_per_col[col] += 1;
+        }
+    }
 
-fn find_galaxies(map: &Matrix) -> Vec<(usize, usize)> {
-    let mut galaxies = Vec::new();
-    (0..map.len()).for_each(|row| {
-        (0..map[row].len()).for_each(|col| {
-            if map[row][col] == '#' {
-                galaxies.push((row, col));
-            }
-        });
-    });
-    galaxies
+    axis_distances(&galaxies_per_row, expansion_rate)
+        + axis_distances(&galaxies_per_col, expansion_rate)
 }
 
-fn calculate_distances(map: &Matrix, expansion_rate: usize) -> usize {
-    let rows_without_galaxies = find_rows_without_galaxies(map);
-    let columns_without_galaxies = find_columns_without_galaxies(map);
-
-    let galaxies = find_galaxies(map);
-    galaxies
-        .iter()
-        .combinations(2)
-        .map(|el| {
-            let first = el[0];
-            let second = el[1];
-            let count_rows_without_galaxies = rows_without_galaxies
-                .iter()
-                .filter(|row| (first.0.min(second.0)..second.0.max(first.0)).contains(row))
-                .count();
-            let count_columns_without_galaxies = columns_without_galaxies
-                .iter()
-                .filter(|col| (first.1.min(second.1)..second.1.max(first.1)).contains(col))
-                .count();
-            let diff_row =
-                first.0.abs_diff(second.0) + count_rows_without_galaxies * (expansion_rate - 1);
-            let diff_col =
-                first.1.abs_diff(second.1) + count_columns_without_galaxies * (expansion_rate - 1);
-
-            diff_row + diff_col
-        })
-        .sum()
+/// Walks the lines in order, so galaxies come out sorted by their expanded coordinate. Each
+/// one is `coordinate` away from those before it, minus where they are, which is a prefix sum.
+fn axis_distances(galaxies_per_line: &[usize], expansion_rate: usize) -> usize {
+    let (mut coordinate, mut seen, mut coordinate_sum, mut total) = (0, 0, 0, 0);
+    for galaxies in galaxies_per_line {
+        if *galaxies == 0 {
+            coordinate += expansion_rate;
+            continue;
+        }
+        total += galaxies * (seen * coordinate - coordinate_sum);
+        seen += galaxies;
+        coordinate_sum += galaxies * coordinate;
+        coordinate += 1;
+    }
+    total
 }
 
 #[cfg(test)]
@@ -84,4 +63,12 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(8410));
     }
+
+    #[test]
+    fn test_expansion_rates() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        assert_eq!(solve(&input, 10), 1030);
+        // without expansion it's just the pairwise Manhattan distances
+        assert_eq!(solve("#.#\n...\n#..\n", 1), 2 + 2 + 4);
+    }
 }
@@ -1,29 +1,38 @@
-use std::collections::HashMap;
-
+use advent_of_code::helpers::rng::XorShift;
+use advent_of_code::template::param;
 use itertools::Itertools;
 
 advent_of_code::solution!(12);
 
 pub fn part_one(input: &str) -> Option<u64> {
-    let problems = parse_input(input);
-    let mut memo = HashMap::new();
-    let result = problems
-        .iter()
-        .map(|(code, groups)| count_arrangements(code, groups, &mut memo))
-        .sum();
-    Some(result)
+    Some(solve(input, 1))
 }
 
 pub fn part_two(input: &str) -> Option<u64> {
-    let problems = parse_input(input);
-    let mut memo = HashMap::new();
-    let result = problems
-        .iter()
-        .map(|(code, groups)| {
-            count_arrangements(&[*code; 5].join("?"), &groups.repeat(5), &mut memo)
+    // `cargo solve 12 --param unfold=<factor>` unfolds the rows some other number of times
+    Some(solve(input, param("unfold").unwrap_or(5)))
+}
+
+fn solve(input: &str, unfold_factor: usize) -> u64 {
+    let rows = parse_input(input);
+    let mut table = Arrangements::default();
+    // `--param list=<row>` shows the arrangements behind one row, counting from 1
+    if let Some(row) = param::<usize>("list") {
+        match row.checked_sub(1).and_then(|row| rows.get(row)) {
+            Some((springs, groups)) => {
+                table.fill(springs, groups, unfold_factor);
+                list_arrangements(&table, springs, groups, unfold_factor);
+            }
+            None => eprintln!("warning: there is no row {row} to list"),
+        }
+    }
+
+    rows.iter()
+        .map(|(springs, groups)| {
+            table.fill(springs, groups, unfold_factor);
+            table.count()
         })
-        .sum();
-    Some(result)
+        .sum()
 }
 
 fn parse_input(input: &str) -> Vec<(&str, Vec<usize>)> {
@@ -40,52 +49,149 @@ fn parse_input(input: &str) -> Vec<(&str, Vec<usize>)> {
         .collect_vec()
 }
 
-fn count_arrangements(code: &str, groups: &[usize], memo: &mut HashMap<String, u64>) -> u64 {
-    let memo_key = code.to_owned() + &groups.iter().map(|el| el.to_string()).join(",");
-    if let Some(memoized) = memo.get(&memo_key) {
-        return *memoized;
+/// `ways[i][j]` is the number of ways to fit `groups[j..]` into `springs[i..]`. The table is
+/// kept between rows so its buffers are only ever grown, never reallocated per row.
+#[derive(Default)]
+struct Arrangements {
+    ways: Vec<u64>,
+    springs: Vec<u8>,
+    groups: Vec<usize>,
+}
+
+impl Arrangements {
+    /// Fills the table for the row repeated `factor` times, with the springs joined by `?`.
+    fn fill(&mut self, springs: &str, groups: &[usize], factor: usize) {
+        self.springs.clear();
+        self.groups.clear();
+        for copy in 0..factor {
+            if copy > 0 {
+                self.springs.push(b'?');
+            }
+            self.springs.extend_from_slice(springs.as_bytes());
+            self.groups.extend_from_slice(groups);
+        }
+        let (length, group_count) = (self.springs.len(), self.groups.len());
+        self.ways.clear();
+        self.ways.resize((length + 1) * (group_count + 1), 0);
+        self.ways[length * (group_count + 1) + group_count] = 1;
+
+        for position in (0..length).rev() {
+            for group in 0..=group_count {
+                let ways = self.skip(position, group) + self.place(position, group);
+                self.ways[position * (group_count + 1) + group] = ways;
+            }
+        }
+    }
+
+    fn count(&self) -> u64 {
+        self.ways(0, 0)
     }
-    if code.is_empty() {
-        return if groups.is_empty() { 1 } else { 0 };
+
+    fn ways(&self, position: usize, group: usize) -> u64 {
+        self.ways[position * (self.groups.len() + 1) + group]
     }
 
-    let arrangements = match code.chars().next() {
-        Some('.') => count_arrangements(&code[1..], groups, memo),
-        Some('?') => {
-            count_arrangements(&code[1..], groups, memo)
-                + count_arrangements(&("#".to_owned() + &code[1..]), groups, memo)
+    /// Arrangements where the spring at `position` is operational.
+    fn skip(&self, position: usize, group: usize) -> u64 {
+        if self.springs[position] == b'#' {
+            0
+        } else {
+            self.ways(position + 1, group)
         }
-        Some('#') => {
-            if groups.is_empty() {
-                return 0;
-            }
+    }
 
-            let expected = groups.first().unwrap();
+    /// Arrangements where `groups[group]` starts at `position`.
+    fn place(&self, position: usize, group: usize) -> u64 {
+        match self.after_group(position, group) {
+            Some(next) => self.ways(next, group + 1),
+            None => 0,
+        }
+    }
 
-            // if we expected more characters than we have
-            if *expected > code.len() {
-                return 0;
-            }
-            // if I can't make a chunk of selected length
-            if code[0..*expected].chars().any(|c| c == '.') {
-                return 0;
-            }
-            // if the code has exactly the expected length
-            if code.len() == *expected {
-                // validate if this is the last group
-                return if groups.len() == 1 { 1 } else { 0 };
-            }
-            // if the char right after the chunk is # we will not have the expected length
-            if code.chars().nth(*expected) == Some('#') {
-                return 0;
+    /// Where the next group could start if `groups[group]` is placed at `position`, which
+    /// needs enough non-operational springs followed by the end or a non-damaged one.
+    fn after_group(&self, position: usize, group: usize) -> Option<usize> {
+        let size = *self.groups.get(group)?;
+        let end = position + size;
+        if self.springs[position] == b'.'
+            || end > self.springs.len()
+            || self.springs[position..end].contains(&b'.')
+            || self.springs.get(end) == Some(&b'#')
+        {
+            return None;
+        }
+        Some((end + 1).min(self.springs.len()))
+    }
+
+    /// Every concrete arrangement, for debugging. Only walks branches that lead somewhere.
+    fn enumerate(&self) -> Vec<String> {
+        let mut arrangements = Vec::new();
+        self.enumerate_from(0, 0, &mut Vec::new(), &mut arrangements);
+        arrangements
+    }
+
+    fn enumerate_from(
+        &self,
+        position: usize,
+        group: usize,
+        current: &mut Vec<u8>,
+        arrangements: &mut Vec<String>,
+    ) {
+        if position == self.springs.len() {
+            arrangements.push(String::from_utf8(current.clone()).unwrap());
+            return;
+        }
+        if self.skip(position, group) > 0 {
+            current.push(b'.');
+            self.enumerate_from(position + 1, group, current, arrangements);
+            current.pop();
+        }
+        if self.place(position, group) > 0 {
+            let next = self.after_group(position, group).unwrap();
+            let size = self.groups[group];
+            current.extend(std::iter::repeat_n(b'#', size));
+            current.extend(std::iter::repeat_n(b'.', next - position - size));
+            self.enumerate_from(next, group + 1, current, arrangements);
+            current.truncate(position);
+        }
+    }
+
+    /// One arrangement drawn uniformly at random, for debugging rows with too many to list.
+    fn sample(&self, rng: &mut XorShift) -> Option<String> {
+        if self.count() == 0 {
+            return None;
+        }
+        let (mut position, mut group) = (0, 0);
+        let mut arrangement = Vec::new();
+        while position < self.springs.len() {
+            let pick = rng.below(self.ways(position, group));
+            if pick < self.skip(position, group) {
+                arrangement.push(b'.');
+                position += 1;
+            } else {
+                let next = self.after_group(position, group).unwrap();
+                let size = self.groups[group];
+                arrangement.extend(std::iter::repeat_n(b'#', size));
+                arrangement.extend(std::iter::repeat_n(b'.', next - position - size));
+                position = next;
+                group += 1;
             }
-            // skip expected + 1 and remove one group to continue
-            return count_arrangements(&code[*expected + 1..], &groups[1..], memo);
         }
-        _ => unreachable!(),
-    };
-    memo.insert(memo_key, arrangements);
-    arrangements
+        String::from_utf8(arrangement).ok()
+    }
+}
+
+/// Prints a row's arrangements to stderr, or a few random ones if there are too many to list.
+fn list_arrangements(table: &Arrangements, springs: &str, groups: &[usize], factor: usize) {
+    eprintln!("{springs} {groups:?} unfolded {factor}x: {}", table.count());
+    if table.count() <= 10 {
+        table.enumerate().iter().for_each(|a| eprintln!("  {a}"));
+    } else {
+        let mut rng = XorShift::new(1);
+        (0..3)
+            .filter_map(|_| table.sample(&mut rng))
+            .for_each(|a| eprintln!("  ~ {a}"));
+    }
 }
 
 #[cfg(test)]
@@ -103,4 +209,20 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(525152));
     }
+
+    #[test]
+    fn test_arrangements() {
+        let mut table = Arrangements::default();
+        table.fill("?###????????", &[3, 2, 1], 1);
+        let arrangements = table.enumerate();
+        assert_eq!(arrangements.len() as u64, table.count());
+        assert_eq!(arrangements.len(), 10);
+        assert!(arrangements.contains(&".###.##.#...".to_string()));
+        let sample = table.sample(&mut XorShift::new(42)).unwrap();
+        assert!(arrangements.contains(&sample));
+
+        table.fill("???.###", &[1, 1, 3], 2);
+        assert_eq!(table.springs, b"???.###????.###");
+        assert_eq!(table.groups, vec![1, 1, 3, 1, 1, 3]);
+    }
 }
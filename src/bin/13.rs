@@ -1,64 +1,148 @@
-use advent_of_code::helpers::matrix::{read_matrices, transpose, Matrix};
+use advent_of_code::helpers::parse::sections;
+use itertools::Itertools;
 
 advent_of_code::solution!(13);
 
 pub fn part_one(input: &str) -> Option<usize> {
-    let allowed_differences = 0;
-    let patterns = read_matrices(input);
-
-    let result = patterns
-        .iter()
-        .map(|pattern| {
-            find_mirror_row(pattern, allowed_differences) * 100
-                + find_mirror_row(&transpose(pattern), allowed_differences)
-        })
-        .sum();
-
-    Some(result)
+    summarize(input, 0)
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    let expected_differences = 1;
-    let patterns = read_matrices(input);
-
-    let result = patterns
-        .iter()
-        .map(|pattern| {
-            find_mirror_row(pattern, expected_differences) * 100
-                + find_mirror_row(&transpose(pattern), expected_differences)
+    summarize(input, 1)
+}
+
+fn summarize(input: &str, smudges: u32) -> Option<usize> {
+    let result = sections(input)
+        .enumerate()
+        .map(|(index, section)| {
+            let axis = Pattern::parse(section)?.reflection(smudges);
+            axis.map(|axis| axis.summary())
+                .map_err(|reason| format!("pattern {}: {reason}", index + 1))
         })
-        .sum();
+        .sum::<Result<usize, String>>();
 
-    Some(result)
+    match result {
+        Ok(summary) => Some(summary),
+        Err(diagnostic) => {
+            eprintln!("error: {diagnostic}");
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Orientation {
+    /// A mirror between two rows.
+    Horizontal,
+    /// A mirror between two columns.
+    Vertical,
 }
 
-fn find_mirror_row(pattern: &Matrix, expected_differences: usize) -> usize {
-    let mut total_differences;
-    for row in 1..pattern.len() {
-        let first_half = &pattern[0..row];
-        let second_half = &pattern[row..];
-        total_differences = 0;
-        for i in 0..first_half.len().min(second_half.len()) {
-            let first_line = &first_half[first_half.len() - i - 1];
-            let second_line = &second_half[i];
-            if second_line != first_line {
-                total_differences += count_differences(first_line, second_line);
-
-                if total_differences > expected_differences {
-                    break;
+/// A line of reflection, lying just before row or column `position`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Axis {
+    orientation: Orientation,
+    position: usize,
+    /// The `(row, col)` tiles that must be flipped for the reflection to be perfect, always
+    /// picked from the half before the mirror.
+    smudges: Vec<(usize, usize)>,
+}
+
+impl Axis {
+    fn summary(&self) -> usize {
+        match self.orientation {
+            Orientation::Horizontal => self.position * 100,
+            Orientation::Vertical => self.position,
+        }
+    }
+}
+
+/// A pattern of ash and rocks, with every row and column stored as a bitmask of its rocks.
+struct Pattern {
+    rows: Vec<u64>,
+    cols: Vec<u64>,
+}
+
+impl Pattern {
+    fn parse(input: &str) -> Result<Self, String> {
+        let lines = input.lines().collect_vec();
+        let width = lines.first().map_or(0, |line| line.len());
+        if width > 64 || lines.len() > 64 {
+            return Err(format!("{}x{width} is too big", lines.len()));
+        }
+
+        let mut rows = vec![0; lines.len()];
+        let mut cols = vec![0; width];
+        for (row, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(format!("row {} is not {width} wide", row + 1));
+            }
+            for (col, tile) in line.bytes().enumerate() {
+                if tile == b'#' {
+                    rows[row] |= 1 << col;
+                    cols[col] |= 1 << row;
                 }
             }
         }
-        if total_differences == expected_differences {
-            return row;
-        }
+        Ok(Self { rows, cols })
+    }
+
+    /// Every axis the pattern reflects along with exactly `smudges` tiles flipped.
+    fn axes(&self, smudges: u32) -> Vec<Axis> {
+        let horizontal = mirrors(&self.rows, smudges)
+            .into_iter()
+            .map(|(position, flips)| Axis {
+                orientation: Orientation::Horizontal,
+                position,
+                smudges: flips,
+            });
+        let vertical = mirrors(&self.cols, smudges)
+            .into_iter()
+            .map(|(position, flips)| Axis {
+                orientation: Orientation::Vertical,
+                position,
+                smudges: flips.into_iter().map(|(col, row)| (row, col)).collect(),
+            });
+        horizontal.chain(vertical).collect()
     }
 
-    0
+    /// The one axis the pattern reflects along with exactly `smudges` tiles flipped.
+    fn reflection(&self, smudges: u32) -> Result<Axis, String> {
+        let mut axes = self.axes(smudges);
+        match axes.len() {
+            1 => Ok(axes.remove(0)),
+            0 => Err(format!("no reflection with {smudges} smudges")),
+            count => Err(format!("{count} reflections with {smudges} smudges")),
+        }
+    }
 }
 
-fn count_differences(first: &[char], second: &[char]) -> usize {
-    (0..first.len()).filter(|i| first[*i] != second[*i]).count()
+/// Positions between `lines` where the mirrored lines differ in exactly `smudges` bits, along
+/// with `(line, bit)` for each of those bits on the side before the mirror.
+fn mirrors(lines: &[u64], smudges: u32) -> Vec<(usize, Vec<(usize, usize)>)> {
+    (1..lines.len())
+        .filter_map(|position| {
+            let pairs = (0..position).rev().zip(position..lines.len());
+            let differences = pairs
+                .clone()
+                .map(|(before, after)| (lines[before] ^ lines[after]).count_ones())
+                .sum::<u32>();
+            if differences != smudges {
+                return None;
+            }
+
+            let flips = pairs
+                .flat_map(|(before, after)| {
+                    let difference = lines[before] ^ lines[after];
+                    (0..64)
+                        .filter(move |bit| difference & (1 << bit) != 0)
+                        .map(move |bit| (before, bit))
+                })
+                .sorted()
+                .collect();
+            Some((position, flips))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -76,4 +160,59 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(400));
     }
+
+    #[test]
+    fn test_axes() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let patterns = sections(&input)
+            .map(|section| Pattern::parse(section).unwrap())
+            .collect_vec();
+
+        assert_eq!(
+            patterns[0].reflection(1),
+            Ok(Axis {
+                orientation: Orientation::Horizontal,
+                position: 3,
+                smudges: vec![(0, 0)],
+            })
+        );
+        assert_eq!(
+            patterns[1].reflection(1),
+            Ok(Axis {
+                orientation: Orientation::Horizontal,
+                position: 1,
+                smudges: vec![(0, 4)],
+            })
+        );
+
+        let symmetric = Pattern::parse("#..#\n#..#\n").unwrap();
+        assert_eq!(
+            symmetric.reflection(0),
+            Err("2 reflections with 0 smudges".to_string())
+        );
+        let plain = Pattern::parse("#.\n..\n").unwrap();
+        assert_eq!(
+            plain.reflection(0),
+            Err("no reflection with 0 smudges".to_string())
+        );
+        assert_eq!(part_one("#.\n..\n"), None);
+
+        // a single rock can be smudged away to fold either way
+        assert_eq!(
+            plain.axes(1),
+            vec![
+                Axis {
+                    orientation: Orientation::Horizontal,
+                    position: 1,
+                    smudges: vec![(0, 0)],
+                },
+                Axis {
+                    orientation: Orientation::Vertical,
+                    position: 1,
+                    smudges: vec![(0, 0)],
+                },
+            ]
+        );
+        assert!(plain.reflection(1).is_err());
+    }
 }
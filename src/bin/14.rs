@@ -1,130 +1,131 @@
-use std::collections::HashMap;
-
-use advent_of_code::helpers::matrix::{read_matrix, reverse_rows, transpose, Matrix};
-use itertools::Itertools;
+use advent_of_code::helpers::cycle::find_cycle;
 
 advent_of_code::solution!(14);
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let mapping = read_matrix(input);
-    let mapping = slide_rocks_north(&mapping, &mut HashMap::new());
+/// Rounded rocks, one bitset per row where bit `c` is column `c`.
+type Rocks = Vec<u128>;
 
-    let result: usize = mapping
-        .iter()
-        .rev()
-        .enumerate()
-        .map(|(i, r)| r.iter().filter(|c| **c == 'O').count() * (i + 1))
-        .sum();
+/// The fixed part of the dish: cube-shaped rocks, laid out like `Rocks`. Rows are single
+/// `u128` words, so every tilt moves a whole row or column of rocks per bit operation.
+struct Platform {
+    cubes: Vec<u128>,
+    mask: u128,
+}
 
-    Some(result)
+pub fn part_one(input: &str) -> Option<usize> {
+    let (platform, mut rocks) = Platform::parse(input)?;
+    platform.tilt_north(&mut rocks);
+    Some(load(&rocks))
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    let mut memo = HashMap::new();
-    let mut mapping = read_matrix(input);
-    // starts repeating after 118 cycles! :D
-    for _ in 0..118 {
-        mapping = slide_rocks_north(&mapping, &mut memo);
-        mapping = slide_rocks_west(&mapping, &mut memo);
-        mapping = slide_rocks_south(&mapping, &mut memo);
-        mapping = slide_rocks_east(&mapping, &mut memo);
-    }
-
-    let result: usize = mapping
-        .iter()
-        .rev()
-        .enumerate()
-        .map(|(i, r)| r.iter().filter(|c| **c == 'O').count() * (i + 1))
-        .sum();
-
-    Some(result)
+    let (platform, rocks) = Platform::parse(input)?;
+    let cycle = find_cycle(rocks, |rocks| platform.spin(rocks));
+    Some(load(cycle.nth_state(1_000_000_000)))
 }
 
-fn matrix_to_memo_key(matrix: &Matrix, direction: &str) -> String {
-    matrix_to_memoized(matrix) + direction
-}
+impl Platform {
+    /// Returns `None` for dishes wider than the 128 columns a row word can hold.
+    fn parse(input: &str) -> Option<(Self, Rocks)> {
+        let width = input.lines().next()?.len();
+        if width > 128 {
+            return None;
+        }
 
-fn matrix_from_memoized(memoized: &str) -> Matrix {
-    memoized
-        .split('-')
-        .map(|row| row.chars().collect_vec())
-        .collect_vec()
-}
+        let (mut cubes, mut rocks) = (Vec::new(), Vec::new());
+        for line in input.lines() {
+            let (mut cube_row, mut rock_row) = (0, 0);
+            for (col, tile) in line.bytes().enumerate() {
+                match tile {
+                    b'#' => cube_row |= 1 << col,
+                    b'O' => rock_row |= 1 << col,
+                    _ => {}
+                }
+            }
+            cubes.push(cube_row);
+            rocks.push(rock_row);
+        }
 
-fn matrix_to_memoized(matrix: &Matrix) -> String {
-    matrix.iter().map(|row| row.iter().join("")).join("-")
-}
+        let mask = if width == 128 {
+            u128::MAX
+        } else {
+            (1 << width) - 1
+        };
+        Some((Self { cubes, mask }, rocks))
+    }
 
-fn slide_rocks_north(mapping: &Matrix, memo: &mut HashMap<String, String>) -> Matrix {
-    let memo_key = matrix_to_memo_key(mapping, "north");
-    if let Some(memoized) = memo.get(&memo_key) {
-        return matrix_from_memoized(memoized);
+    fn free(&self, rocks: &[u128], row: usize) -> u128 {
+        !(rocks[row] | self.cubes[row]) & self.mask
     }
-    let transposed = transpose(mapping);
-    let response = transpose(&slide_rocks_west(&transposed, memo));
-    memo.insert(memo_key, matrix_to_memoized(&response));
-    response
-}
 
-fn slide_rocks_east(mapping: &Matrix, memo: &mut HashMap<String, String>) -> Matrix {
-    let memo_key = matrix_to_memo_key(mapping, "east");
-    if let Some(memoized) = memo.get(&memo_key) {
-        return matrix_from_memoized(memoized);
+    fn tilt_north(&self, rocks: &mut [u128]) {
+        // rows above `row` have already settled, so rocks only need to bubble up until they stop
+        for row in 1..rocks.len() {
+            for above in (0..row).rev() {
+                let moving = rocks[above + 1] & self.free(rocks, above);
+                if moving == 0 {
+                    break;
+                }
+                rocks[above + 1] ^= moving;
+                rocks[above] |= moving;
+            }
+        }
     }
-    let reversed = reverse_rows(mapping);
-    let response = reverse_rows(&slide_rocks_west(&reversed, memo));
-    memo.insert(memo_key, matrix_to_memoized(&response));
-    response
-}
 
-fn slide_rocks_south(mapping: &Matrix, memo: &mut HashMap<String, String>) -> Matrix {
-    let memo_key = matrix_to_memo_key(mapping, "south");
-    if let Some(memoized) = memo.get(&memo_key) {
-        return matrix_from_memoized(memoized);
+    fn tilt_south(&self, rocks: &mut [u128]) {
+        for row in (0..rocks.len().saturating_sub(1)).rev() {
+            for below in row + 1..rocks.len() {
+                let moving = rocks[below - 1] & self.free(rocks, below);
+                if moving == 0 {
+                    break;
+                }
+                rocks[below - 1] ^= moving;
+                rocks[below] |= moving;
+            }
+        }
     }
-    let transposed_reversed = reverse_rows(&transpose(mapping));
-    let response = transpose(&reverse_rows(&slide_rocks_west(&transposed_reversed, memo)));
-    memo.insert(memo_key, matrix_to_memoized(&response));
-    response
-}
 
-fn slide_rocks_west(mapping: &Matrix, memo: &mut HashMap<String, String>) -> Matrix {
-    let memo_key = matrix_to_memo_key(mapping, "west");
-    if let Some(memoized) = memo.get(&memo_key) {
-        return matrix_from_memoized(memoized);
+    fn tilt_west(&self, rocks: &mut [u128]) {
+        for row in 0..rocks.len() {
+            loop {
+                let moving = rocks[row] & (self.free(rocks, row) << 1);
+                if moving == 0 {
+                    break;
+                }
+                rocks[row] ^= moving | (moving >> 1);
+            }
+        }
     }
 
-    let mut mapping_after_slide = Vec::new();
-
-    for r in mapping {
-        let mut last_position = 0;
-        let mut row = Vec::new();
-        while last_position < r.len() {
-            if let Some((rock_position, _)) =
-                r[last_position..].iter().find_position(|c| **c == '#')
-            {
-                let count_rocks = r[last_position..last_position + rock_position]
-                    .iter()
-                    .filter(|c| **c == 'O')
-                    .count();
-                row.append(&mut vec!['O'; count_rocks]);
-                row.append(&mut vec!['.'; rock_position - count_rocks]);
-                row.push('#');
-
-                last_position += rock_position + 1;
-            } else {
-                let count_rocks = r[last_position..].iter().filter(|c| **c == 'O').count();
-                row.append(&mut vec!['O'; count_rocks]);
-                row.append(&mut vec!['.'; r[last_position..].len() - count_rocks]);
-
-                break;
+    fn tilt_east(&self, rocks: &mut [u128]) {
+        for row in 0..rocks.len() {
+            loop {
+                let moving = rocks[row] & (self.free(rocks, row) >> 1);
+                if moving == 0 {
+                    break;
+                }
+                rocks[row] ^= moving | (moving << 1);
             }
         }
-        mapping_after_slide.push(row);
     }
-    memo.insert(memo_key, matrix_to_memoized(&mapping_after_slide));
 
-    mapping_after_slide
+    fn spin(&self, rocks: &Rocks) -> Rocks {
+        let mut rocks = rocks.clone();
+        self.tilt_north(&mut rocks);
+        self.tilt_west(&mut rocks);
+        self.tilt_south(&mut rocks);
+        self.tilt_east(&mut rocks);
+        rocks
+    }
+}
+
+fn load(rocks: &[u128]) -> usize {
+    rocks
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, row)| row.count_ones() as usize * (i + 1))
+        .sum()
 }
 
 #[cfg(test)]
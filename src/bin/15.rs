@@ -1,79 +1,125 @@
-use std::collections::HashMap;
-
-use itertools::Itertools;
+use advent_of_code::helpers::hash::FastMap;
 
 advent_of_code::solution!(15);
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let response = input
-        .trim_end_matches('\n')
-        .split(',')
-        .map(|hash| {
-            let mut total: u32 = 0;
-            for c in hash.chars() {
-                total = ((total + c as u32) * 17) % 256;
-            }
-            total
-        })
-        .sum::<u32>();
+    let response = steps(input).map(|step| hash(step) as u32).sum::<u32>();
 
     Some(response)
 }
 
-struct BoxInfo {
-    label: String,
+pub fn part_two(input: &str) -> Option<u32> {
+    let mut boxes = LensBoxes::default();
+    for step in steps(input) {
+        match step.split_once('=') {
+            Some((label, focal_length)) => boxes.insert(label, focal_length.parse().ok()?),
+            None => {
+                boxes.remove(step.strip_suffix('-')?);
+            }
+        }
+    }
+
+    Some(boxes.focusing_power())
+}
+
+fn steps(input: &str) -> impl Iterator<Item = &str> {
+    input.trim_end_matches('\n').split(',')
+}
+
+/// The Holiday ASCII String Helper algorithm.
+fn hash(value: &str) -> u8 {
+    value
+        .bytes()
+        .fold(0, |acc, byte| acc.wrapping_add(byte).wrapping_mul(17))
+}
+
+const BOX_COUNT: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Lens<'a> {
+    label: &'a str,
     focal_length: u32,
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut box_map: HashMap<u32, Vec<BoxInfo>> = HashMap::new();
-
-    input.trim_end_matches('\n').split(',').for_each(|hash| {
-        let separator_pos = hash.find(|c| ['-', '='].contains(&c)).unwrap();
-        let (label, rest) = hash.split_at(separator_pos);
-        let (separator, focal_length_str) = rest.split_at(1);
-
-        let box_number = label
-            .chars()
-            .fold(0, |acc, cur| ((acc + cur as u32) * 17) % 256);
-
-        if separator == "=" {
-            let focal_length = focal_length_str.parse::<u32>().unwrap();
-            let box_info = BoxInfo {
-                label: label.to_string(),
-                focal_length,
-            };
-
-            let matching_box = box_map.entry(box_number).or_default();
-            if let Some((matching_pos, _)) = matching_box
-                .iter()
-                .find_position(|item| item.label.starts_with(label))
-            {
-                matching_box[matching_pos] = box_info;
-            } else {
-                matching_box.push(box_info);
+/// The 256 boxes of lenses, each keeping its lenses in the order they were first inserted.
+/// Removed lenses leave an empty slot behind, so `index` can point every label straight at
+/// its slot. A box is compacted once most of its slots are empty.
+struct LensBoxes<'a> {
+    boxes: Vec<Vec<Option<Lens<'a>>>>,
+    lens_counts: Vec<usize>,
+    index: FastMap<&'a str, usize>,
+}
+
+impl Default for LensBoxes<'_> {
+    fn default() -> Self {
+        Self {
+            boxes: vec![Vec::new(); BOX_COUNT],
+            lens_counts: vec![0; BOX_COUNT],
+            index: FastMap::default(),
+        }
+    }
+}
+
+impl<'a> LensBoxes<'a> {
+    /// Replaces the lens labelled `label` in place, or adds it behind the others in its box.
+    fn insert(&mut self, label: &'a str, focal_length: u32) {
+        let lens = Lens {
+            label,
+            focal_length,
+        };
+        let slots = &mut self.boxes[hash(label) as usize];
+        match self.index.get(label) {
+            Some(slot) => slots[*slot] = Some(lens),
+            None => {
+                self.index.insert(label, slots.len());
+                slots.push(Some(lens));
+                self.lens_counts[hash(label) as usize] += 1;
             }
-        } else if let Some(matching_box) = box_map.get_mut(&box_number) {
-            matching_box.retain(|item| !item.label.starts_with(label));
         }
-    });
-
-    let result = box_map
-        .iter()
-        .flat_map(|(box_number, box_infos)| {
-            box_infos
-                .iter()
-                .enumerate()
-                .map(|(idx, info)| (*box_number + 1) * (idx as u32 + 1) * info.focal_length)
-        })
-        .sum();
-
-    Some(result)
+    }
+
+    /// Takes the lens labelled `label` out of its box, returning its focal length.
+    fn remove(&mut self, label: &str) -> Option<u32> {
+        let slot = self.index.remove(label)?;
+        let box_number = hash(label) as usize;
+        let lens = self.boxes[box_number][slot].take()?;
+        self.lens_counts[box_number] -= 1;
+        if self.boxes[box_number].len() > 2 * self.lens_counts[box_number] + 8 {
+            self.compact(box_number);
+        }
+        Some(lens.focal_length)
+    }
+
+    /// The lenses in `box_number`, front to back.
+    fn lenses(&self, box_number: usize) -> impl Iterator<Item = &Lens<'a>> {
+        self.boxes[box_number].iter().flatten()
+    }
+
+    fn focusing_power(&self) -> u32 {
+        (0..BOX_COUNT)
+            .flat_map(|box_number| {
+                self.lenses(box_number)
+                    .enumerate()
+                    .map(move |(slot, lens)| {
+                        (box_number as u32 + 1) * (slot as u32 + 1) * lens.focal_length
+                    })
+            })
+            .sum()
+    }
+
+    fn compact(&mut self, box_number: usize) {
+        let slots = &mut self.boxes[box_number];
+        slots.retain(Option::is_some);
+        for (slot, lens) in slots.iter().flatten().enumerate() {
+            self.index.insert(lens.label, slot);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::helpers::rng::XorShift;
 
     #[test]
     fn test_part_one() {
@@ -86,4 +132,88 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(145));
     }
+
+    #[test]
+    fn test_exact_labels() {
+        // `ab` must neither replace nor remove `abc`
+        assert_eq!(
+            part_two("abc=2,ab=1,ab-\n"),
+            Some(2 * hash("abc") as u32 + 2)
+        );
+        assert_eq!(part_two("ab=1,abc=2,ab=3\n"), part_two("ab=3,abc=2\n"));
+    }
+
+    // the straightforward model, scanning each box for its labels
+    #[derive(Default)]
+    struct NaiveBoxes {
+        boxes: FastMap<u8, Vec<(String, u32)>>,
+    }
+
+    impl NaiveBoxes {
+        fn insert(&mut self, label: &str, focal_length: u32) {
+            let lenses = self.boxes.entry(hash(label)).or_default();
+            match lenses.iter_mut().find(|(other, _)| other == label) {
+                Some(lens) => lens.1 = focal_length,
+                None => lenses.push((label.to_string(), focal_length)),
+            }
+        }
+
+        fn remove(&mut self, label: &str) -> Option<u32> {
+            let lenses = self.boxes.entry(hash(label)).or_default();
+            let position = lenses.iter().position(|(other, _)| other == label)?;
+            Some(lenses.remove(position).1)
+        }
+
+        fn lenses(&self, box_number: u8) -> Vec<(&str, u32)> {
+            self.boxes.get(&box_number).map_or(Vec::new(), |lenses| {
+                lenses
+                    .iter()
+                    .map(|(label, focal_length)| (label.as_str(), *focal_length))
+                    .collect()
+            })
+        }
+    }
+
+    #[test]
+    fn test_against_naive_model() {
+        // labels sharing prefixes and boxes: `ab`, `pc` and `ot` all go in box 3
+        let labels = ["a", "ab", "abc", "rn", "cm", "qp", "pc", "ot", "x"];
+        for seed in 1..=50u64 {
+            let mut rng = XorShift::new(seed);
+
+            let mut boxes = LensBoxes::default();
+            let mut model = NaiveBoxes::default();
+            for _ in 0..200 {
+                let label = labels[rng.below(labels.len() as u64) as usize];
+                if rng.below(3) == 0 {
+                    assert_eq!(boxes.remove(label), model.remove(label));
+                } else {
+                    let focal_length = rng.below(9) as u32 + 1;
+                    boxes.insert(label, focal_length);
+                    model.insert(label, focal_length);
+                }
+
+                let lens_box = hash(label);
+                let lenses = boxes
+                    .lenses(lens_box as usize)
+                    .map(|lens| (lens.label, lens.focal_length))
+                    .collect::<Vec<_>>();
+                assert_eq!(lenses, model.lenses(lens_box), "seed {seed}");
+            }
+
+            let expected = (0..=255u8)
+                .flat_map(|box_number| {
+                    model
+                        .lenses(box_number)
+                        .iter()
+                        .enumerate()
+                        .map(|(slot, (_, focal))| {
+                            (box_number as u32 + 1) * (slot as u32 + 1) * focal
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .sum::<u32>();
+            assert_eq!(boxes.focusing_power(), expected, "seed {seed}");
+        }
+    }
 }
@@ -1,14 +1,8 @@
-use std::collections::HashSet;
-
-use itertools::Itertools;
-use rayon::{
-    iter::{ParallelBridge, ParallelIterator},
-    *,
-};
+use rayon::prelude::*;
 
 advent_of_code::solution!(16);
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
     North,
     East,
@@ -16,241 +10,163 @@ enum Direction {
     West,
 }
 
-#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
-pub struct Cell {
-    pub row: usize,
-    pub col: usize,
-    pub value: char,
+/// The tiles a beam crosses after leaving an optic, up to and including the next optic it
+/// hits. `end` is that optic's tile together with the direction the beam arrives in.
+#[derive(Debug, Clone)]
+struct Segment {
+    tiles: Vec<usize>,
+    end: Option<(usize, Direction)>,
 }
 
-pub fn part_one(input: &str) -> Option<usize> {
-    let layout = parse_input(input);
-
-    let mut collisions = HashSet::new();
-    let mut trail = HashSet::new();
-    navigate(
-        Cell {
-            row: 0,
-            col: 0,
-            value: '.',
-        },
-        if cfg!(test) {
-            Direction::East
-        } else {
-            Direction::South
-        },
-        &layout,
-        &mut collisions,
-        &mut trail,
-    );
-
-    // energize_cells(&layout, &trail);
+struct Contraption {
+    tiles: Vec<u8>,
+    width: usize,
+    height: usize,
+    // indexed by `tile * 4 + outgoing direction`, only filled in for optic tiles
+    segments: Vec<Option<Segment>>,
+}
 
-    Some(trail.len())
+pub fn part_one(input: &str) -> Option<usize> {
+    let contraption = Contraption::new(input);
+    Some(contraption.energize(0, Direction::East))
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    let layout = parse_input(input);
-    let length = if cfg!(test) { 10 } else { 110 };
+    let contraption = Contraption::new(input);
+    let (width, height) = (contraption.width, contraption.height);
 
     let mut starting_points = Vec::new();
-    (0..length).for_each(|el| {
+    (0..width).for_each(|col| {
         // top row
-        starting_points.push((0, el, Direction::South));
-
+        starting_points.push((col, Direction::South));
+        // bottom row
+        starting_points.push(((height - 1) * width + col, Direction::North));
+    });
+    (0..height).for_each(|row| {
         // west column
-        starting_points.push((el, 0, Direction::East));
-
+        starting_points.push((row * width, Direction::East));
         // east column
-        starting_points.push((el, length - 1, Direction::West));
-
-        // bottom row
-        starting_points.push((length - 1, el, Direction::North));
+        starting_points.push((row * width + width - 1, Direction::West));
     });
 
-    let result = starting_points
-        .iter()
-        .par_bridge()
-        .map(|el| {
-            let mut collisions = HashSet::new();
-            let mut trail = HashSet::new();
-            navigate(
-                Cell {
-                    row: el.0,
-                    col: el.1,
-                    value: '.',
-                },
-                el.2,
-                &layout,
-                &mut collisions,
-                &mut trail,
-            );
+    starting_points
+        .par_iter()
+        .map(|(tile, direction)| contraption.energize(*tile, *direction))
+        .max()
+}
 
-            trail.len()
-        })
-        .max();
+impl Contraption {
+    fn new(input: &str) -> Self {
+        let width = input.lines().next().map_or(0, str::len);
+        let tiles = input.lines().flat_map(str::bytes).collect::<Vec<_>>();
+        let height = tiles.len() / width.max(1);
+
+        let mut contraption = Self {
+            tiles,
+            width,
+            height,
+            segments: Vec::new(),
+        };
+        contraption.segments = (0..contraption.tiles.len() * 4)
+            .map(|key| {
+                let tile = key / 4;
+                is_optic(contraption.tiles[tile])
+                    .then(|| contraption.trace_segment(tile, DIRECTIONS[key % 4]))
+            })
+            .collect();
+        contraption
+    }
 
-    // energize_cells(&layout, &trail);
+    fn step(&self, tile: usize, direction: Direction) -> Option<usize> {
+        let (row, col) = (tile / self.width, tile % self.width);
+        match direction {
+            Direction::North if row > 0 => Some(tile - self.width),
+            Direction::East if col + 1 < self.width => Some(tile + 1),
+            Direction::South if row + 1 < self.height => Some(tile + self.width),
+            Direction::West if col > 0 => Some(tile - 1),
+            _ => None,
+        }
+    }
 
-    result
-}
+    fn trace_segment(&self, from: usize, direction: Direction) -> Segment {
+        let mut tiles = Vec::new();
+        let mut current = from;
+        while let Some(next) = self.step(current, direction) {
+            tiles.push(next);
+            if is_optic(self.tiles[next]) {
+                return Segment {
+                    tiles,
+                    end: Some((next, direction)),
+                };
+            }
+            current = next;
+        }
+        Segment { tiles, end: None }
+    }
 
-fn _energize_cells(map: &[Cell], trail: &HashSet<Cell>) {
-    let length: usize = if cfg!(test) { 10 } else { 110 };
-    (0..length).for_each(|row| {
-        (0..length).for_each(|col| {
-            let cell = map
-                .iter()
-                .find(|item| item.col == col && item.row == row)
-                .unwrap_or_else(|| panic!("expected to find item in map {} {}", row, col));
-            let value = if trail.contains(cell) {
-                if cell.value == '.' {
-                    '#'
-                } else {
-                    cell.value
+    /// Counts the tiles energized by a beam entering `tile` while heading in `direction`.
+    fn energize(&self, tile: usize, direction: Direction) -> usize {
+        let mut energized = vec![false; self.tiles.len()];
+        let mut followed = vec![false; self.tiles.len() * 4];
+        energized[tile] = true;
+        let mut count = 1;
+
+        let mut beams = vec![(tile, direction)];
+        while let Some((tile, incoming)) = beams.pop() {
+            for &outgoing in deflect(self.tiles[tile], incoming) {
+                let key = tile * 4 + outgoing as usize;
+                if followed[key] {
+                    continue;
                 }
-            } else {
-                cell.value
-            };
-            print!("{}", value);
-        });
-        println!();
-    });
-}
+                followed[key] = true;
+
+                // only the entry tile can be empty space, its segment isn't worth caching
+                let traced;
+                let segment = match &self.segments[key] {
+                    Some(segment) => segment,
+                    None => {
+                        traced = self.trace_segment(tile, outgoing);
+                        &traced
+                    }
+                };
+                for &crossed in &segment.tiles {
+                    if !energized[crossed] {
+                        energized[crossed] = true;
+                        count += 1;
+                    }
+                }
+                beams.extend(segment.end);
+            }
+        }
 
-fn navigate(
-    current_cell: Cell,
-    direction: Direction,
-    map: &[Cell],
-    collisions: &mut HashSet<String>,
-    trail: &mut HashSet<Cell>,
-) {
-    let hash_key = format!("{}-{}-{:?}", current_cell.row, current_cell.col, direction);
-    if collisions.contains(&hash_key) {
-        return;
+        count
     }
-    collisions.insert(hash_key);
-
-    let next_collision = get_next_collision(current_cell, &direction, map);
-    get_trail(current_cell, direction, next_collision, map)
-        .iter()
-        .for_each(|item| {
-            trail.insert(*item);
-        });
-    let Some(collision) = next_collision else {
-        return;
-    };
-    let next_directions = treat_collision(collision.value, &direction);
-    next_directions
-        .iter()
-        .for_each(|next_direction| navigate(collision, *next_direction, map, collisions, trail));
 }
 
-fn parse_input(input: &str) -> Vec<Cell> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(row, line)| {
-            line.chars()
-                .enumerate()
-                .map(|(col, value)| Cell { row, col, value })
-                .collect_vec()
-        })
-        .collect_vec()
-}
-
-fn get_trail(from: Cell, direction: Direction, to: Option<Cell>, map: &[Cell]) -> Vec<Cell> {
-    let to = match to {
-        Some(val) => val,
-        _ => match direction {
-            Direction::North => Cell {
-                row: 0,
-                col: from.col,
-                value: '.',
-            },
-            Direction::East => Cell {
-                row: from.row,
-                col: usize::MAX,
-                value: '.',
-            },
-            Direction::South => Cell {
-                row: usize::MAX,
-                col: from.col,
-                value: '.',
-            },
-            Direction::West => Cell {
-                row: from.row,
-                col: 0,
-                value: '.',
-            },
-        },
-    };
-    map.iter()
-        .filter(|item| {
-            (from.row.min(to.row)..=from.row.max(to.row)).contains(&item.row)
-                && (from.col.min(to.col)..=from.col.max(to.col)).contains(&item.col)
-        })
-        .copied()
-        .collect_vec()
-}
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
 
-fn treat_collision(value: char, direction: &Direction) -> Vec<Direction> {
-    match direction {
-        Direction::North => match value {
-            '\\' => vec![Direction::West],
-            '/' => vec![Direction::East],
-            '-' => vec![Direction::West, Direction::East],
-            _ => unreachable!(),
-        },
-        Direction::East => match value {
-            '\\' => vec![Direction::South],
-            '/' => vec![Direction::North],
-            '|' => vec![Direction::South, Direction::North],
-            _ => unreachable!(),
-        },
-        Direction::South => match value {
-            '\\' => vec![Direction::East],
-            '/' => vec![Direction::West],
-            '-' => vec![Direction::West, Direction::East],
-            _ => unreachable!(),
-        },
-        Direction::West => match value {
-            '\\' => vec![Direction::North],
-            '/' => vec![Direction::South],
-            '|' => vec![Direction::North, Direction::South],
-            _ => unreachable!(),
-        },
-    }
+fn is_optic(tile: u8) -> bool {
+    matches!(tile, b'/' | b'\\' | b'|' | b'-')
 }
 
-fn get_next_collision(cell: Cell, direction: &Direction, map: &[Cell]) -> Option<Cell> {
-    match direction {
-        Direction::North => map
-            .iter()
-            .filter(|item| item.row < cell.row && item.col == cell.col)
-            .sorted_by_key(|item| item.row)
-            .rev()
-            .find(|item| item.value != '.' && item.value != '|')
-            .copied(),
-        Direction::East => map
-            .iter()
-            .filter(|item| item.row == cell.row && item.col > cell.col)
-            .sorted_by_key(|item| item.col)
-            .find(|item| item.value != '.' && item.value != '-')
-            .copied(),
-        Direction::South => map
-            .iter()
-            .filter(|item| item.row > cell.row && item.col == cell.col)
-            .sorted_by_key(|item| item.row)
-            .find(|item| item.value != '.' && item.value != '|')
-            .copied(),
-        Direction::West => map
-            .iter()
-            .filter(|item| item.row == cell.row && item.col < cell.col)
-            .sorted_by_key(|item| item.col)
-            .rev()
-            .find(|item| item.value != '.' && item.value != '-')
-            .copied(),
+fn deflect(tile: u8, direction: Direction) -> &'static [Direction] {
+    use Direction::*;
+    match (tile, direction) {
+        (b'/', North) | (b'\\', South) => &[East],
+        (b'/', East) | (b'\\', West) => &[North],
+        (b'/', South) | (b'\\', North) => &[West],
+        (b'/', West) | (b'\\', East) => &[South],
+        (b'|', East | West) => &[North, South],
+        (b'-', North | South) => &[West, East],
+        (_, North) => &[North],
+        (_, East) => &[East],
+        (_, South) => &[South],
+        (_, West) => &[West],
     }
 }
 
@@ -269,4 +185,10 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(51));
     }
+
+    #[test]
+    fn test_entry_tile_optic() {
+        // the beam enters the top-left mirror heading east and is sent south straight away
+        assert_eq!(part_one("\\..\n...\n...\n"), Some(3));
+    }
 }
@@ -1,85 +1,161 @@
-use std::{
-    cmp::Reverse,
-    collections::{BinaryHeap, HashMap},
-};
+use std::{cmp::Reverse, collections::BinaryHeap};
 
 use itertools::Itertools;
 
 advent_of_code::solution!(17);
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let map = parse_input(input);
-    let result = modified_dijkstra(&map, 1, 3);
+pub fn part_one(input: &str) -> Option<u32> {
+    let map = HeatMap::new(input);
+    let route = map.find_route(&Crucible::REGULAR)?;
 
-    Some(result)
+    Some(route.heat_loss)
 }
 
-pub fn part_two(input: &str) -> Option<i32> {
-    let map = parse_input(input);
-    let result = modified_dijkstra(&map, 4, 10);
+pub fn part_two(input: &str) -> Option<u32> {
+    let map = HeatMap::new(input);
+    let route = map.find_route(&Crucible::ULTRA)?;
 
-    Some(result)
+    Some(route.heat_loss)
 }
 
-fn parse_input(input: &str) -> Vec<Vec<u8>> {
-    input
-        .lines()
-        .map(|line| line.chars().map(|c| c as u8).collect_vec())
-        .collect_vec()
+/// How far a crucible may roll in a straight line. It always has to turn 90 degrees after a
+/// run, so a run ends after `min_straight..=max_straight` blocks.
+#[derive(Debug, Clone, Copy)]
+struct Crucible {
+    min_straight: usize,
+    max_straight: usize,
 }
 
-fn modified_dijkstra(map: &[Vec<u8>], min_range: isize, max_range: isize) -> i32 {
-    let mut distance_map = HashMap::new();
-    let mut heap = BinaryHeap::new();
-    let starting_cell = (0, 0);
-    let starting_direction = (0, 0); // this is invalid but it won't matter for the first looo
-    heap.push((Reverse(0), (starting_cell, starting_direction)));
+impl Crucible {
+    const REGULAR: Self = Self {
+        min_straight: 1,
+        max_straight: 3,
+    };
+    const ULTRA: Self = Self {
+        min_straight: 4,
+        max_straight: 10,
+    };
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Route {
+    heat_loss: u32,
+    // every block entered, from the lava pool to the factory, excluding the starting block
+    path: Vec<(usize, usize)>,
+}
+
+struct HeatMap {
+    blocks: Vec<u8>,
+    rows: usize,
+    cols: usize,
+}
 
-    while let Some((Reverse(cur_distance), ((row, col), direction))) = heap.pop() {
-        let (rows, cols) = (map.len(), map[0].len());
+// the axis a crucible arrived on, it has to leave on the other one
+const HORIZONTAL: usize = 0;
+const VERTICAL: usize = 1;
+
+impl HeatMap {
+    fn new(input: &str) -> Self {
+        let rows = input.lines().count();
+        let blocks = input
+            .lines()
+            .flat_map(|line| line.bytes().map(|b| b - b'0'))
+            .collect_vec();
+        let cols = blocks.len() / rows.max(1);
+        Self { blocks, rows, cols }
+    }
 
-        if (row, col) == (rows - 1, cols - 1) {
-            return cur_distance;
+    /// A* over `(block, arrival axis)` states, stored densely as `block * 2 + axis`. The
+    /// heuristic is the Manhattan distance times the cheapest block, which never overestimates.
+    fn find_route(&self, crucible: &Crucible) -> Option<Route> {
+        let target = self.blocks.len().checked_sub(1)?;
+        let cheapest = *self.blocks.iter().min()? as u32;
+        let estimate = |block: usize| {
+            let (row, col) = (block / self.cols, block % self.cols);
+            ((self.rows - 1 - row) + (self.cols - 1 - col)) as u32 * cheapest
+        };
+
+        let mut heat_loss = vec![u32::MAX; self.blocks.len() * 2];
+        let mut previous = vec![usize::MAX; self.blocks.len() * 2];
+        let mut heap = BinaryHeap::new();
+        // the crucible starts out free to go either way
+        for axis in [HORIZONTAL, VERTICAL] {
+            heat_loss[axis] = 0;
+            heap.push(Reverse((estimate(0), 0, axis)));
         }
 
-        for (shift_row, shift_col) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
-            // stop now if going in the same direction or attempting a reverse
-            if direction == (shift_row, shift_col) || direction == (-shift_row, -shift_col) {
+        while let Some(Reverse((_, cur_heat_loss, state))) = heap.pop() {
+            if cur_heat_loss > heat_loss[state] {
                 continue;
             }
+            let block = state / 2;
+            if block == target {
+                return Some(Route {
+                    heat_loss: cur_heat_loss,
+                    path: self.reconstruct_path(&previous, state),
+                });
+            }
 
-            let mut next_distance = cur_distance;
-
-            for range in 1..=max_range {
-                let new_row = (row as isize + shift_row * range) as usize;
-                let new_col = (col as isize + shift_col * range) as usize;
-
-                // prevent out of bounds
-                if new_row >= rows || new_col >= cols {
-                    continue;
+            let next_axis = 1 - state % 2;
+            let shifts = if next_axis == HORIZONTAL {
+                [(0, -1), (0, 1)]
+            } else {
+                [(-1, 0), (1, 0)]
+            };
+            for (shift_row, shift_col) in shifts {
+                let mut next_heat_loss = cur_heat_loss;
+                for range in 1..=crucible.max_straight as isize {
+                    let Some(next_block) = self.shift(block, shift_row * range, shift_col * range)
+                    else {
+                        break;
+                    };
+                    next_heat_loss += self.blocks[next_block] as u32;
+                    if (range as usize) < crucible.min_straight {
+                        continue;
+                    }
+
+                    let next_state = next_block * 2 + next_axis;
+                    if next_heat_loss < heat_loss[next_state] {
+                        heat_loss[next_state] = next_heat_loss;
+                        previous[next_state] = state;
+                        heap.push(Reverse((
+                            next_heat_loss + estimate(next_block),
+                            next_heat_loss,
+                            next_state,
+                        )));
+                    }
                 }
+            }
+        }
 
-                next_distance += (map[new_row][new_col] - b'0') as i32;
-
-                if range < min_range {
-                    continue;
-                }
+        None
+    }
 
-                let next_cell_with_direction = ((new_row, new_col), (shift_row, shift_col));
-                let known_dist = distance_map
-                    .get(&next_cell_with_direction)
-                    .copied()
-                    .unwrap_or(i32::MAX);
+    fn shift(&self, block: usize, shift_row: isize, shift_col: isize) -> Option<usize> {
+        let row = (block / self.cols).checked_add_signed(shift_row)?;
+        let col = (block % self.cols).checked_add_signed(shift_col)?;
+        (row < self.rows && col < self.cols).then_some(row * self.cols + col)
+    }
 
-                if next_distance < known_dist {
-                    distance_map.insert(next_cell_with_direction, next_distance);
-                    heap.push((Reverse(next_distance), next_cell_with_direction));
-                }
+    /// Walks the `previous` links back to the start, filling in the blocks crossed by each run.
+    fn reconstruct_path(&self, previous: &[usize], mut state: usize) -> Vec<(usize, usize)> {
+        let mut path = Vec::new();
+        while previous[state] != usize::MAX {
+            let (to, from) = (state / 2, previous[state] / 2);
+            let (to_row, to_col) = ((to / self.cols) as isize, (to % self.cols) as isize);
+            let (from_row, from_col) = ((from / self.cols) as isize, (from % self.cols) as isize);
+            let (step_row, step_col) = ((to_row - from_row).signum(), (to_col - from_col).signum());
+            let (mut row, mut col) = (to_row, to_col);
+            while (row, col) != (from_row, from_col) {
+                path.push((row as usize, col as usize));
+                row -= step_row;
+                col -= step_col;
             }
+            state = previous[state];
         }
+        path.reverse();
+        path
     }
-
-    0
 }
 
 #[cfg(test)]
@@ -97,4 +173,20 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(94));
     }
+
+    #[test]
+    fn test_route() {
+        let map = HeatMap::new(&advent_of_code::template::read_file("examples", DAY));
+        let route = map.find_route(&Crucible::REGULAR).unwrap();
+        let path_heat_loss: u32 = route
+            .path
+            .iter()
+            .map(|(row, col)| map.blocks[row * map.cols + col] as u32)
+            .sum();
+        assert_eq!(path_heat_loss, route.heat_loss);
+        assert_eq!(route.path.last(), Some(&(12, 12)));
+
+        // a single row can't be crossed by a crucible that needs four blocks to stop
+        assert_eq!(HeatMap::new("111\n").find_route(&Crucible::ULTRA), None);
+    }
 }
@@ -1,38 +1,35 @@
-use std::{collections::HashMap, ops::RangeInclusive};
-
+use advent_of_code::helpers::{
+    hash::FastMap,
+    parse::{braced, ints, sections},
+};
 use itertools::Itertools;
 
 advent_of_code::solution!(19);
 
 pub fn part_one(input: &str) -> Option<u64> {
     let (workflows, parts) = parse_input(input)?;
+    let tree = DecisionTree::compile(&workflows, RATING_BOUNDS)?;
     let result = parts
         .iter()
-        .map(|part| {
-            let mut current_step = "in";
-
-            while !["A", "R"].contains(&current_step) {
-                current_step = apply_rules(&workflows[current_step].rules, part);
-            }
-
-            if current_step == "A" {
-                part.x + part.m + part.a + part.s
-            } else {
-                0
-            }
-        })
+        .filter(|part| tree.accepts(part))
+        .map(|part| part.iter().sum::<u64>())
         .sum();
     Some(result)
 }
 
-#[derive(Debug)]
-struct Part {
-    x: u64,
-    m: u64,
-    a: u64,
-    s: u64,
+pub fn part_two(input: &str) -> Option<u64> {
+    let (workflows, _) = parse_input(input)?;
+    count_combinations(&workflows, RATING_BOUNDS)
 }
 
+/// Ratings for the `x`, `m`, `a` and `s` categories, in that order.
+type Part = [u64; 4];
+
+/// Half-open `[low, high)` rating ranges, one per category.
+type Bounds = [(u64, u64); 4];
+
+const RATING_BOUNDS: Bounds = [(1, 4001); 4];
+
 // .... it was at this moment he learned about Rust Limetime properties! :D
 #[derive(Debug)]
 struct Workflow<'a> {
@@ -41,226 +38,242 @@ struct Workflow<'a> {
 
 #[derive(Debug)]
 struct Rule<'a> {
-    part_name: char,
+    // `None` for the final rule, which always applies
+    condition: Option<Condition>,
+    destination: &'a str,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Condition {
+    category: usize,
     comparator: char,
     value: u64,
-    destination: &'a str,
 }
 
-fn parse_input(input: &str) -> Option<(HashMap<&str, Workflow>, Vec<Part>)> {
-    let (workflows, parts) = input.split_once("\n\n")?;
+fn parse_input(input: &str) -> Option<(FastMap<&str, Workflow<'_>>, Vec<Part>)> {
+    let mut sections = sections(input);
+    let (workflows, parts) = (sections.next()?, sections.next().unwrap_or_default());
 
     let parsed_workflows = workflows
         .lines()
-        .try_fold(HashMap::new(), |mut acc, line| {
-            let (name, steps) = line.split_once('{')?;
-            let rules = steps
-                .trim_end_matches('}')
-                .split(',')
-                .filter_map(|step| {
-                    // possible passing rule
-                    if let Some((condition, destination)) = step.split_once(':') {
-                        let part_name = condition.chars().next()?;
-                        let comparator = condition.chars().nth(1)?;
-                        let value = condition[2..].parse().ok()?;
-
-                        Some(Rule {
-                            part_name,
-                            comparator,
-                            value,
-                            destination,
-                        })
-                    } else {
-                        // rule without condition, just destination
-                        Some(Rule {
-                            part_name: '_',
-                            comparator: '_',
-                            value: 0,
-                            destination: step,
-                        })
-                    }
-                })
-                .collect_vec();
-            acc.insert(name, Workflow { rules });
-            Some(acc)
-        })?;
+        .map(|line| {
+            let (name, steps) = braced(line)?;
+            let rules = steps.split(',').map(parse_rule).collect::<Option<_>>()?;
+            Some((name, Workflow { rules }))
+        })
+        .collect::<Option<_>>()?;
 
     let parsed_parts = parts
         .lines()
-        .map(|line| {
-            let values = line
-                .trim_start_matches('{')
-                .trim_end_matches('}')
-                .split(',')
-                .filter_map(|part| {
-                    part.split_once('=')
-                        .and_then(|(_, value)| value.parse().ok())
-                })
-                .collect_vec();
-
-            Part {
-                x: values[0],
-                m: values[1],
-                a: values[2],
-                s: values[3],
-            }
-        })
-        .collect_vec();
+        .map(|line| ints(line).collect_vec().try_into().ok())
+        .collect::<Option<_>>()?;
 
     Some((parsed_workflows, parsed_parts))
 }
 
-fn rule_passes(rule: &Rule, value: u64) -> bool {
-    match rule.comparator {
-        '<' => value < rule.value,
-        '>' => value > rule.value,
-        _ => unreachable!(),
-    }
-}
-
-fn apply_rules<'a>(rules: &[Rule<'a>], part: &Part) -> &'a str {
-    let passing_rule = rules
-        .iter()
-        .find(|rule| match rule.part_name {
-            'x' => rule_passes(rule, part.x),
-            'm' => rule_passes(rule, part.m),
-            'a' => rule_passes(rule, part.a),
-            's' => rule_passes(rule, part.s),
-            '_' => true,
-            _ => unreachable!(),
-        })
-        .unwrap();
-    passing_rule.destination
-}
-
-pub fn part_two(input: &str) -> Option<u64> {
-    parse_input(input).map(|(workflows, _)| {
-        resolve_state(&workflows, State::default())
-            .into_iter()
-            .flat_map(|state| {
-                if state.path.last().unwrap() == &"A" {
-                    Some(count_ranges(&state.range))
-                } else {
-                    None
-                }
-            })
-            .sum()
+fn parse_rule(step: &str) -> Option<Rule<'_>> {
+    let Some((condition, destination)) = step.split_once(':') else {
+        // rule without condition, just destination
+        return Some(Rule {
+            condition: None,
+            destination: step,
+        });
+    };
+    let category = "xmas".find(condition.get(..1)?)?;
+    let comparator = condition
+        .chars()
+        .nth(1)
+        .filter(|c| ['<', '>'].contains(c))?;
+    let value = condition[2..].parse().ok()?;
+
+    Some(Rule {
+        condition: Some(Condition {
+            category,
+            comparator,
+            value,
+        }),
+        destination,
     })
 }
 
-#[derive(Clone, Default)]
-struct PartRange {
-    x: Vec<RangeInclusive<u64>>,
-    m: Vec<RangeInclusive<u64>>,
-    a: Vec<RangeInclusive<u64>>,
-    s: Vec<RangeInclusive<u64>>,
+/// Workflows compiled into binary decisions. Identical subtrees are stored once, so `nodes`
+/// is really a DAG, and `root` is the entry point for parts within `bounds`.
+#[derive(Debug)]
+struct DecisionTree {
+    nodes: Vec<Node>,
+    root: usize,
+    bounds: Bounds,
 }
 
-#[derive(Clone)]
-struct State<'a> {
-    range: PartRange,
-    path: Vec<&'a str>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Node {
+    Accept,
+    Reject,
+    /// Parts rated below `threshold` in `category` continue at `below`, the rest at `above`.
+    Split {
+        category: usize,
+        threshold: u64,
+        below: usize,
+        above: usize,
+    },
 }
 
-impl<'a> Default for State<'a> {
-    fn default() -> Self {
-        Self {
-            range: Default::default(),
-            path: vec!["in"],
-        }
-    }
-}
+impl DecisionTree {
+    /// Compiles the workflows starting at `in` for parts within `bounds`. Conditions that
+    /// every part reaching them passes (or fails) are dropped, as are splits whose two sides
+    /// end up identical. Returns `None` if a rule points at a missing workflow or they loop.
+    fn compile(workflows: &FastMap<&str, Workflow>, bounds: Bounds) -> Option<Self> {
+        let mut compiler = Compiler {
+            workflows,
+            nodes: Vec::new(),
+            interned: FastMap::default(),
+        };
+        let root = if bounds.iter().any(|(low, high)| low >= high) {
+            compiler.intern(Node::Reject)
+        } else {
+            compiler.compile("in", 0, bounds, 0)?
+        };
 
-fn resolve_range(rule: &Rule, should_pass: bool) -> (char, RangeInclusive<u64>) {
-    let floor = 1;
-    let ceil = 4000;
+        Some(Self {
+            nodes: compiler.nodes,
+            root,
+            bounds,
+        })
+    }
 
-    let range = match rule.comparator {
-        '>' => {
-            if should_pass {
-                (rule.value + 1)..=ceil
-            } else {
-                floor..=rule.value
+    fn accepts(&self, part: &Part) -> bool {
+        let mut current = self.root;
+        loop {
+            match self.nodes[current] {
+                Node::Accept => return true,
+                Node::Reject => return false,
+                Node::Split {
+                    category,
+                    threshold,
+                    below,
+                    above,
+                } => {
+                    current = if part[category] < threshold {
+                        below
+                    } else {
+                        above
+                    };
+                }
             }
         }
-        '<' => {
-            if should_pass {
-                floor..=(rule.value - 1)
-            } else {
-                rule.value..=ceil
+    }
+
+    /// The disjoint boxes of ratings that end up accepted.
+    fn accepted_boxes(&self) -> Vec<Bounds> {
+        let mut boxes = Vec::new();
+        let mut pending = vec![(self.root, self.bounds)];
+        while let Some((current, bounds)) = pending.pop() {
+            match self.nodes[current] {
+                Node::Accept => boxes.push(bounds),
+                Node::Reject => {}
+                Node::Split {
+                    category,
+                    threshold,
+                    below,
+                    above,
+                } => {
+                    let (lower, upper) = split_bounds(bounds, category, threshold);
+                    pending.push((below, lower));
+                    pending.push((above, upper));
+                }
             }
         }
-        _ => unreachable!(),
-    };
-
-    (rule.part_name, range)
-}
-
-fn update_ranges(state: &mut State, range: (char, RangeInclusive<u64>)) {
-    match range.0 {
-        'x' => state.range.x.push(range.1),
-        'm' => state.range.m.push(range.1),
-        'a' => state.range.a.push(range.1),
-        's' => state.range.s.push(range.1),
-        _ => unreachable!(),
+        boxes
     }
 }
 
-fn resolve_state<'a>(workflows: &'a HashMap<&str, Workflow>, state: State<'a>) -> Vec<State<'a>> {
-    let mut next_states = vec![];
-
-    let current = state.path.last().expect("empty paths are not allowed.");
+struct Compiler<'w, 'a> {
+    workflows: &'w FastMap<&'a str, Workflow<'a>>,
+    nodes: Vec<Node>,
+    interned: FastMap<Node, usize>,
+}
 
-    // we are at the end.
-    if *current == "A" || *current == "R" {
-        return vec![state];
+impl Compiler<'_, '_> {
+    fn intern(&mut self, node: Node) -> usize {
+        *self.interned.entry(node).or_insert_with(|| {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        })
     }
 
-    let workflow = workflows.get(current).expect("undefined workflow");
-
-    let mut current_state = state.clone();
-    let mut reject_range;
-
-    for rule in &workflow.rules {
-        if rule.part_name == '_' {
-            // reached the end so we haven't passed any rule
-            current_state.path.push(rule.destination);
-            next_states.extend(resolve_state(workflows, current_state.clone()));
-        } else {
-            let mut pass_state = current_state.clone();
-            update_ranges(&mut pass_state, resolve_range(rule, true));
-
-            pass_state.path.push(rule.destination);
-            next_states.extend(resolve_state(workflows, pass_state));
+    /// Compiles `workflow` from its `rule`-th rule onwards, for parts within `bounds`.
+    fn compile(
+        &mut self,
+        workflow: &str,
+        rule: usize,
+        bounds: Bounds,
+        depth: usize,
+    ) -> Option<usize> {
+        match workflow {
+            "A" => return Some(self.intern(Node::Accept)),
+            "R" => return Some(self.intern(Node::Reject)),
+            // more nested workflows than exist means the workflows loop
+            _ if depth > self.workflows.len() => return None,
+            _ => {}
+        }
 
-            // also update the failing case
-            reject_range = resolve_range(rule, false);
-            update_ranges(&mut current_state, reject_range);
+        let workflows = self.workflows;
+        let current = workflows.get(workflow)?.rules.get(rule)?;
+        let Some(condition) = current.condition else {
+            return self.compile(current.destination, 0, bounds, depth + 1);
+        };
+
+        // every condition becomes "rating < threshold"
+        let (threshold, passes_below) = match condition.comparator {
+            '<' => (condition.value, true),
+            _ => (condition.value + 1, false),
+        };
+        let (lower, upper) = split_bounds(bounds, condition.category, threshold);
+        let mut branch = |side: Bounds, passes: bool| {
+            let (low, high) = side[condition.category];
+            if low >= high {
+                // dead branch, no part in bounds gets here
+                Some(None)
+            } else if passes {
+                self.compile(current.destination, 0, side, depth + 1)
+                    .map(Some)
+            } else {
+                self.compile(workflow, rule + 1, side, depth).map(Some)
+            }
+        };
+        let below = branch(lower, passes_below)?;
+        let above = branch(upper, !passes_below)?;
+
+        match (below, above) {
+            (Some(below), Some(above)) if below != above => Some(self.intern(Node::Split {
+                category: condition.category,
+                threshold,
+                below,
+                above,
+            })),
+            (Some(only), _) | (None, Some(only)) => Some(only),
+            (None, None) => unreachable!("bounds are never empty on both sides"),
         }
     }
-
-    next_states
 }
 
-fn collapse_range(range: &[RangeInclusive<u64>]) -> u64 {
-    let range = range.iter().fold((1, 4000), |mut acc, curr| {
-        if curr.start() > &acc.0 {
-            acc.0 = *curr.start()
-        }
-        if curr.end() < &acc.1 {
-            acc.1 = *curr.end()
-        }
-        acc
-    });
-
-    1 + range.1 - range.0
+fn split_bounds(bounds: Bounds, category: usize, threshold: u64) -> (Bounds, Bounds) {
+    let (low, high) = bounds[category];
+    let (mut lower, mut upper) = (bounds, bounds);
+    lower[category] = (low, high.min(threshold));
+    upper[category] = (low.max(threshold), high);
+    (lower, upper)
 }
 
-fn count_ranges(ranges: &PartRange) -> u64 {
-    collapse_range(&ranges.x)
-        * collapse_range(&ranges.m)
-        * collapse_range(&ranges.a)
-        * collapse_range(&ranges.s)
+/// Counts the rating combinations within `bounds` that the workflows accept.
+fn count_combinations(workflows: &FastMap<&str, Workflow>, bounds: Bounds) -> Option<u64> {
+    let tree = DecisionTree::compile(workflows, bounds)?;
+    let total = tree
+        .accepted_boxes()
+        .iter()
+        .map(|bounds| bounds.iter().map(|(low, high)| high - low).product::<u64>())
+        .sum();
+    Some(total)
 }
 
 #[cfg(test)]
@@ -278,4 +291,63 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(167409079868000));
     }
+
+    // the straightforward interpreter, to check the compiled tree against
+    fn interpret(workflows: &FastMap<&str, Workflow>, part: &Part) -> bool {
+        let mut current = "in";
+        while !["A", "R"].contains(&current) {
+            current = workflows[current]
+                .rules
+                .iter()
+                .find(|rule| match rule.condition {
+                    Some(Condition {
+                        category,
+                        comparator: '<',
+                        value,
+                    }) => part[category] < value,
+                    Some(condition) => part[condition.category] > condition.value,
+                    None => true,
+                })
+                .unwrap()
+                .destination;
+        }
+        current == "A"
+    }
+
+    #[test]
+    fn test_custom_bounds() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let (workflows, _) = parse_input(&input).unwrap();
+        let bounds = [(1, 6), (2000, 2004), (1710, 1720), (535, 539)];
+
+        let brute_force = bounds
+            .iter()
+            .map(|(low, high)| *low..*high)
+            .multi_cartesian_product()
+            .filter(|part| interpret(&workflows, &part.clone().try_into().unwrap()))
+            .count();
+        assert_eq!(
+            count_combinations(&workflows, bounds),
+            Some(brute_force as u64)
+        );
+
+        let tree = DecisionTree::compile(&workflows, bounds).unwrap();
+        for accepted in tree.accepted_boxes() {
+            let corner = accepted.map(|(low, _)| low);
+            assert!(tree.accepts(&corner) && interpret(&workflows, &corner));
+        }
+    }
+
+    #[test]
+    fn test_dead_branches() {
+        // `lnx` accepts either way, and `m > 10` always holds once `m > 100`
+        let input = "in{m>100:lnx,R}\nlnx{m>10:A,A}\n\n{x=1,m=200,a=1,s=1}\n";
+        let (workflows, _) = parse_input(input).unwrap();
+        let tree = DecisionTree::compile(&workflows, RATING_BOUNDS).unwrap();
+        assert_eq!(tree.nodes.len(), 3);
+        assert_eq!(
+            tree.accepted_boxes(),
+            vec![[(1, 4001), (101, 4001), (1, 4001), (1, 4001)]]
+        );
+    }
 }
@@ -1,6 +1,9 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::VecDeque;
 
-use advent_of_code::helpers::math::lowest_common_multiple;
+use advent_of_code::helpers::{
+    cycle::{find_cycle_within, first_common_hit, Hits},
+    hash::FastMap,
+};
 use itertools::Itertools;
 
 advent_of_code::solution!(20);
@@ -8,55 +11,259 @@ advent_of_code::solution!(20);
 pub fn part_one(input: &str) -> Option<u64> {
     let mut nodes = parse_input(input);
 
-    let mut pulse_count = (0, 0);
-    (0..1_000).for_each(|i| {
-        let pulses = press_button(&mut nodes, i);
-        pulse_count.0 += pulses.0;
-        pulse_count.1 += pulses.1;
+    let mut pulse_count: (u64, u64) = (0, 0);
+    (0..1_000).for_each(|_| {
+        press_button(&mut nodes, |_, _, pulse, _| match pulse {
+            Pulse::Low => pulse_count.0 += 1,
+            Pulse::High => pulse_count.1 += 1,
+        });
     });
 
     Some(pulse_count.0 * pulse_count.1)
 }
 
 pub fn part_two(input: &str) -> Option<usize> {
-    let mut nodes = parse_input(input);
+    let nodes = parse_input(input);
 
-    // Nice idea from reddit! The parent and grandparents of the 'rx' node are all
-    // conjunction modules. I just need to find the cycle when all grandparents received
-    // Low pulses at the same time
-    let parent = nodes.values().find(|n| n.destinations.contains(&"rx"))?;
+    match presses_until_low_pulse(&nodes, "rx") {
+        Ok(presses) => Some(presses),
+        Err(diagnostic) => {
+            eprintln!("error: {diagnostic}");
+            None
+        }
+    }
+}
 
-    let grandparents = nodes
-        .values()
-        .filter(|n| n.destinations.contains(&parent.name))
-        .map(|n| n.name)
-        .collect_vec();
+/// A subgraph hanging off one broadcaster output that ends in a single module feeding the
+/// collector. `hits` holds the presses, counted from 0, in which the feeder sent a High pulse.
+/// In each of them the collector remembers High for the feeder from the pulse depth
+/// `window.0` until the Low pulse at depth `window.1`.
+#[derive(Debug)]
+struct Counter<'a> {
+    feeder: &'a str,
+    hits: Hits,
+    window: (usize, usize),
+}
 
-    for i in 0..10_000 {
-        press_button(&mut nodes, i);
+/// Finds the first press that makes `target` receive a Low pulse, for networks where `target`
+/// has a single conjunction parent (the collector) and every broadcaster output drives an
+/// independent subgraph feeding one of the collector's inputs. Each subgraph is simulated on
+/// its own until its state repeats, and the resulting cycles are lined up with the CRT.
+///
+/// Pulses are handled in order of depth, so the collector sends Low in a press where every
+/// feeder fires if the feeders' High windows overlap: the last High has to arrive at a
+/// smaller depth than the first Low that ends a window.
+fn presses_until_low_pulse(nodes: &FastMap<&str, Node>, target: &str) -> Result<usize, String> {
+    let parents = senders_to(nodes, target);
+    let collector = match parents[..] {
+        [parent] if matches!(nodes[parent].module, Module::Conjunction(_)) => parent,
+        [parent] => {
+            return Err(format!(
+                "{target}'s only input {parent} is not a conjunction"
+            ))
+        }
+        [] => return Err(format!("no module sends pulses to {target}")),
+        _ => return Err(format!("{target} has several inputs: {parents:?}")),
+    };
+    let broadcaster = nodes
+        .get("broadcaster")
+        .ok_or("the network has no broadcaster")?;
+
+    let mut counters = Vec::new();
+    let mut claimed: FastMap<&str, &str> = FastMap::default();
+    for start in &broadcaster.destinations {
+        let subgraph = reachable_before(nodes, start, collector)?;
+        for name in &subgraph {
+            if let Some(other) = claimed.insert(name, start) {
+                return Err(format!(
+                    "{name} is reachable from both broadcaster outputs {other} and {start}"
+                ));
+            }
+            if let Some(outsider) = senders_to(nodes, name)
+                .into_iter()
+                .find(|sender| *sender != "broadcaster" && !subgraph.contains(sender))
+            {
+                return Err(format!(
+                    "{name} in the subgraph from {start} also listens to {outsider}"
+                ));
+            }
+        }
+        let feeders = subgraph
+            .iter()
+            .filter(|name| nodes[*name].destinations.contains(&collector))
+            .collect_vec();
+        let [feeder] = feeders[..] else {
+            return Err(format!(
+                "the subgraph from {start} feeds {collector} through {feeders:?}"
+            ));
+        };
+        counters.push(simulate_counter(
+            nodes, start, &subgraph, feeder, collector,
+        )?);
     }
 
-    let grandparent_cycles = nodes
+    if let Some(silent) = senders_to(nodes, collector)
+        .into_iter()
+        .find(|sender| counters.iter().all(|counter| counter.feeder != *sender))
+    {
+        return Err(format!(
+            "{collector}'s input {silent} is not driven by the broadcaster"
+        ));
+    }
+
+    if let Some(counter) = counters.iter().find(|counter| !counter.hits.recurs()) {
+        return Err(format!("{} stops sending High pulses", counter.feeder));
+    }
+    let last_high = counters.iter().map(|c| c.window.0).max().unwrap_or(0);
+    let first_low = counters
         .iter()
-        .filter(|(name, _)| grandparents.contains(*name))
-        .flat_map(|(_, node)| {
-            node.cycles
-                .iter()
-                .filter(|(_, val)| val == &Pulse::Low)
-                .tuple_windows()
-                .map(|((first, _), (second, _))| second - first)
-                .collect_vec()
-        })
+        .map(|c| c.window.1)
+        .min()
+        .unwrap_or(usize::MAX);
+    if last_high >= first_low {
+        let windows = counters
+            .iter()
+            .map(|c| format!("{} {}..{}", c.feeder, c.window.0, c.window.1))
+            .join(", ");
+        return Err(format!(
+            "the feeders' High pulses never overlap at {collector}: {windows}"
+        ));
+    }
+    let hits = counters
+        .into_iter()
+        .map(|counter| counter.hits)
         .collect_vec();
+    first_common_hit(&hits, 0)
+        .map(|press| press + 1)
+        .ok_or_else(|| "the counters' cycles never line up".to_string())
+}
+
+fn senders_to<'a>(nodes: &FastMap<&'a str, Node<'a>>, name: &str) -> Vec<&'a str> {
+    nodes
+        .values()
+        .filter(|node| node.destinations.contains(&name))
+        .map(|node| node.name)
+        .sorted()
+        .collect_vec()
+}
+
+/// Every module reachable from `start` without passing through `stop`. Destinations that
+/// aren't modules are skipped, but `start` itself has to be one.
+fn reachable_before<'a>(
+    nodes: &FastMap<&'a str, Node<'a>>,
+    start: &'a str,
+    stop: &str,
+) -> Result<Vec<&'a str>, String> {
+    if !nodes.contains_key(start) {
+        return Err(format!("broadcaster output {start} is not a module"));
+    }
+    let mut seen = vec![start];
+    let mut queue = VecDeque::from([start]);
+    while let Some(name) = queue.pop_front() {
+        for next in nodes.get(name).into_iter().flat_map(|n| &n.destinations) {
+            if *next != stop && nodes.contains_key(next) && !seen.contains(next) {
+                seen.push(next);
+                queue.push_back(next);
+            }
+        }
+    }
+    seen.sort();
+    Ok(seen)
+}
+
+fn simulate_counter<'a>(
+    nodes: &FastMap<&'a str, Node<'a>>,
+    start: &'a str,
+    subgraph: &[&'a str],
+    feeder: &'a str,
+    collector: &'a str,
+) -> Result<Counter<'a>, String> {
+    let mut network = subgraph
+        .iter()
+        .map(|name| (*name, nodes[name].clone()))
+        .collect::<FastMap<_, _>>();
+    let mut broadcaster = nodes["broadcaster"].clone();
+    broadcaster.destinations = vec![start];
+    network.insert("broadcaster", broadcaster);
 
-    Some(lowest_common_multiple(&grandparent_cycles))
+    // `subgraph` is sorted, so the state lists module states in a stable order
+    let state = |network: &FastMap<&'a str, Node<'a>>| {
+        subgraph
+            .iter()
+            .flat_map(|name| network[name].module.state())
+            .collect_vec()
+    };
+    // `find_cycle_within` steps each state once, in order, so `network` always holds the
+    // state it is asked to step from, and press `n` starts from state `n`
+    let mut windows = Vec::new();
+    let (mut ends_high, mut several_windows) = (None, None);
+    let cycle = find_cycle_within(state(&network), MAX_COUNTER_PRESSES, |_| {
+        let (mut high_at, mut window, mut reopened) = (None, None, false);
+        let mut last_pulse = Pulse::Low;
+        press_button(&mut network, |from, to, pulse, depth| {
+            if from == feeder && to == collector {
+                match pulse {
+                    Pulse::High if window.is_some() => reopened = true,
+                    Pulse::High => high_at = high_at.or(Some(depth)),
+                    Pulse::Low => window = window.or(high_at.map(|high| (high, depth))),
+                }
+                last_pulse = pulse;
+            }
+        });
+        if last_pulse == Pulse::High && ends_high.is_none() {
+            ends_high = Some(windows.len() + 1);
+        }
+        if reopened && several_windows.is_none() {
+            several_windows = Some(windows.len() + 1);
+        }
+        windows.push(window);
+        state(&network)
+    })
+    .ok_or_else(|| {
+        format!("the subgraph from {start} didn't repeat within {MAX_COUNTER_PRESSES} presses")
+    })?;
+
+    if let Some(press) = ends_high {
+        // the collector would remember it across presses, so presses aren't independent
+        return Err(format!(
+            "{feeder} ends press {press} with a High pulse to {collector}"
+        ));
+    }
+    if let Some(press) = several_windows {
+        return Err(format!(
+            "{feeder} sends {collector} High pulses twice in press {press}"
+        ));
+    }
+    let fired = windows.iter().flatten().sorted().dedup().collect_vec();
+    let window = match fired[..] {
+        [window] => *window,
+        [] => return Err(format!("{feeder} never sends a High pulse")),
+        ref several => {
+            return Err(format!(
+                "{feeder} sends High pulses in differing windows {several:?}"
+            ))
+        }
+    };
+    Ok(Counter {
+        feeder,
+        hits: Hits {
+            prefix: cycle.prefix,
+            period: cycle.period,
+            positions: (0..windows.len())
+                .filter(|press| windows[*press].is_some())
+                .collect(),
+        },
+        window,
+    })
 }
 
+const MAX_COUNTER_PRESSES: usize = 1 << 20;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum Module<'a> {
     Broadcast,
     FlipFlop(Pulse),
-    Conjunction(HashMap<&'a str, Pulse>),
+    Conjunction(FastMap<&'a str, Pulse>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -70,12 +277,25 @@ struct Node<'a> {
     name: &'a str,
     destinations: Vec<&'a str>,
     module: Module<'a>,
-    cycles: Vec<(usize, Pulse)>,
+}
+
+impl Module<'_> {
+    /// The module's memory, with conjunction inputs in name order.
+    fn state(&self) -> Vec<bool> {
+        match self {
+            Module::Broadcast => Vec::new(),
+            Module::FlipFlop(state) => vec![*state == Pulse::High],
+            Module::Conjunction(map) => map
+                .iter()
+                .sorted_by_key(|(name, _)| **name)
+                .map(|(_, pulse)| *pulse == Pulse::High)
+                .collect(),
+        }
+    }
 }
 
 impl<'a> Node<'a> {
-    fn process_pulse(&mut self, source: &'a str, pulse: Pulse, cycle: usize) -> Option<Pulse> {
-        self.cycles.push((cycle, pulse));
+    fn process_pulse(&mut self, source: &'a str, pulse: Pulse) -> Option<Pulse> {
         match self.module {
             Module::Broadcast => Some(pulse),
             Module::FlipFlop(state) => {
@@ -103,41 +323,30 @@ impl<'a> Node<'a> {
     }
 }
 
-fn press_button(nodes: &mut HashMap<&str, Node>, cycle: usize) -> (u64, u64) {
-    let mut low_pulses_count: u64 = 0;
-    let mut high_pulses_count: u64 = 0;
-
+/// Sends one Low pulse to the broadcaster and processes pulses until the network settles,
+/// reporting every pulse to `observe` as `(from, to, pulse, depth)`. The broadcaster's own
+/// pulses have depth 1, and each pulse a module sends in reply is one deeper.
+fn press_button<'a>(
+    nodes: &mut FastMap<&'a str, Node<'a>>,
+    mut observe: impl FnMut(&'a str, &'a str, Pulse, usize),
+) {
     let mut pulses = VecDeque::new();
-    pulses.push_back(("button", "broadcaster", Pulse::Low));
-
-    while !pulses.is_empty() {
-        let pulse_count = pulses.len();
-        (0..pulse_count).for_each(|_| {
-            let (from, to, pulse) = pulses.pop_front().unwrap();
-            match pulse {
-                Pulse::Low => {
-                    low_pulses_count += 1;
-                }
-                Pulse::High => {
-                    high_pulses_count += 1;
-                }
-            }
-            if let Some(node) = nodes.get_mut(to) {
-                if let Some(new_pulse) = node.process_pulse(from, pulse, cycle) {
-                    node.destinations.iter().for_each(|dest| {
-                        // println!("{:?} -{:?}-> {:?}", to, new_pulse, dest);
-                        pulses.push_back((to, dest, new_pulse))
-                    });
-                }
+    pulses.push_back(("button", "broadcaster", Pulse::Low, 0));
+
+    while let Some((from, to, pulse, depth)) = pulses.pop_front() {
+        observe(from, to, pulse, depth);
+        if let Some(node) = nodes.get_mut(to) {
+            if let Some(new_pulse) = node.process_pulse(from, pulse) {
+                node.destinations
+                    .iter()
+                    .for_each(|dest| pulses.push_back((to, *dest, new_pulse, depth + 1)));
             }
-        })
+        }
     }
-
-    (low_pulses_count, high_pulses_count)
 }
 
-fn parse_input(input: &str) -> HashMap<&str, Node> {
-    let mut nodes = input.lines().fold(HashMap::new(), |mut acc, line| {
+fn parse_input(input: &str) -> FastMap<&str, Node<'_>> {
+    let mut nodes = input.lines().fold(FastMap::default(), |mut acc, line| {
         let (source, destinations) = line.split_once(" -> ").unwrap();
         let destinations = destinations.split(", ").collect_vec();
 
@@ -148,7 +357,6 @@ fn parse_input(input: &str) -> HashMap<&str, Node> {
                     name: source,
                     destinations,
                     module: Module::Broadcast,
-                    cycles: Vec::new(),
                 },
             );
         } else if let Some(stripped) = source.strip_prefix('%') {
@@ -158,7 +366,6 @@ fn parse_input(input: &str) -> HashMap<&str, Node> {
                     name: stripped,
                     destinations,
                     module: Module::FlipFlop(Pulse::Low),
-                    cycles: Vec::new(),
                 },
             );
         } else if let Some(stripped) = source.strip_prefix('&') {
@@ -167,8 +374,7 @@ fn parse_input(input: &str) -> HashMap<&str, Node> {
                 Node {
                     name: stripped,
                     destinations,
-                    module: Module::Conjunction(HashMap::new()),
-                    cycles: Vec::new(),
+                    module: Module::Conjunction(FastMap::default()),
                 },
             );
         }
@@ -206,5 +412,39 @@ mod tests {
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, None);
+
+        let result = part_two(&advent_of_code::template::read_file_part(
+            "examples", DAY, 2,
+        ));
+        assert_eq!(result, Some(1287));
+    }
+
+    #[test]
+    fn test_undecomposable_network() {
+        let shared = "broadcaster -> a, b\n%a -> c\n%b -> c\n%c -> ia, ib\n&ia -> hub\n&ib -> hub\n&hub -> rx\n";
+        let nodes = parse_input(shared);
+        assert_eq!(
+            presses_until_low_pulse(&nodes, "rx"),
+            Err("c in the subgraph from a also listens to b".to_string())
+        );
+
+        let unknown = "broadcaster -> output, a\n%a -> hub\n&hub -> rx\n";
+        assert_eq!(
+            presses_until_low_pulse(&parse_input(unknown), "rx"),
+            Err("broadcaster output output is not a module".to_string())
+        );
+    }
+
+    #[test]
+    fn test_feeders_out_of_step() {
+        // both feeders fire every other press, but the one from xb two pulses later
+        let input = "broadcaster -> xa, xb\n\
+            %xa -> ca, wa\n&wa -> ca\n&ca -> fa\n&fa -> hub\n\
+            %xb -> cb, wb\n&wb -> cb\n&cb -> fb\n&fb -> gb\n&gb -> hb\n&hb -> hub\n\
+            &hub -> rx\n";
+        assert_eq!(
+            presses_until_low_pulse(&parse_input(input), "rx"),
+            Err("the feeders' High pulses never overlap at hub: fa 4..5, hb 6..7".to_string())
+        );
     }
 }
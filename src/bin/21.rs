@@ -1,115 +1,160 @@
-use std::collections::{HashSet, VecDeque};
-
-use advent_of_code::helpers::matrix::{read_matrix, Matrix};
+use advent_of_code::helpers::hash::FastSet;
+use advent_of_code::helpers::poly::Polynomial;
 
 advent_of_code::solution!(21);
 
 pub fn part_one(input: &str) -> Option<usize> {
-    let matrix = read_matrix(input);
+    let garden = Garden::parse(input)?;
     let steps = if cfg!(test) { 6 } else { 64 };
-    let final_positions = walk_steps(&matrix, steps);
-    Some(final_positions.iter().collect::<HashSet<_>>().len() + 1)
+    garden.reachable(&[steps]).first().copied()
 }
 
-pub fn part_two(input: &str) -> Option<i64> {
-    let steps = if cfg!(test) {
-        vec![65]
-    } else {
-        vec![65, 65 + 131, 65 + 131 * 2]
+pub fn part_two(input: &str) -> Option<usize> {
+    let garden = Garden::parse(input)?;
+    // the example garden has rocks in its middle lanes, so the test exercises the fallback
+    let steps = if cfg!(test) { 500 } else { 26501365 };
+
+    let reason = match garden.check_assumptions() {
+        Ok(()) => match garden.extrapolate(steps) {
+            Some(count) => return Some(count),
+            None => "reachable plots don't grow quadratically per tile".to_string(),
+        },
+        Err(reason) => reason,
     };
-    let _results = steps
-        .iter()
-        .map(|steps| {
-            let matrix = read_matrix(input);
-            let final_positions = walk_steps(&matrix, *steps);
-            final_positions.iter().collect::<HashSet<_>>().len()
-        })
-        .collect::<Vec<_>>();
-    // quadratic fit for the given points abovo leaves us with the following polynomial
-    // Some(3882. + 15286.5 * x as f64 + 15196.5 * x.pow(2) as f64)
-    // Solve for x = 202300 to get the solution
-    // wolfram-alpha is your friend
-
-    if cfg!(test) {
-        None
-    } else {
-        Some(622926941971282)
+    // the example always takes this path, no need to repeat it on every test run
+    if !cfg!(test) {
+        eprintln!("warning: {reason}");
+        eprintln!("warning: falling back to a breadth-first search over all {steps} steps");
     }
+    garden.reachable(&[steps]).first().copied()
+}
+
+/// A garden map that repeats infinitely in every direction.
+struct Garden {
+    rocks: Vec<bool>,
+    rows: usize,
+    cols: usize,
+    start: (usize, usize),
 }
 
-fn get_start_position(matrix: &Matrix) -> (isize, isize) {
-    for (row_i, row) in matrix.iter().enumerate() {
-        for (col_i, cell) in row.iter().enumerate() {
-            if cell == &'S' {
-                return (row_i as isize, col_i as isize);
+impl Garden {
+    fn parse(input: &str) -> Option<Self> {
+        let rows = input.lines().count();
+        let cols = input.lines().next()?.len();
+        let mut start = None;
+        let mut rocks = Vec::with_capacity(rows * cols);
+        for (row, line) in input.lines().enumerate() {
+            for (col, tile) in line.bytes().enumerate() {
+                if tile == b'S' {
+                    start = Some((row, col));
+                }
+                rocks.push(tile == b'#');
             }
         }
+
+        Some(Self {
+            rocks,
+            rows,
+            cols,
+            start: start?,
+        })
     }
 
-    unreachable!();
-}
+    fn is_rock(&self, row: isize, col: isize) -> bool {
+        let row = row.rem_euclid(self.rows as isize) as usize;
+        let col = col.rem_euclid(self.cols as isize) as usize;
+        self.rocks[row * self.cols + col]
+    }
 
-fn walk_steps(matrix: &Matrix, steps: u32) -> VecDeque<(isize, isize)> {
-    let start_position = get_start_position(matrix);
-    let mut queue = VecDeque::new();
-    queue.push_back(start_position);
-
-    for _ in 0..steps {
-        let mut visited = HashSet::new();
-        let move_count = queue.len();
-        (0..move_count).for_each(|_| {
-            let position = queue.pop_front().unwrap();
-            if !visited.contains(&position) {
-                visited.insert(position);
-                queue.extend(expand_infinite(matrix, position));
-            }
-        });
+    /// The extrapolation below relies on the walk reaching every neighbouring copy of the
+    /// garden through its middle, at the same time and in a straight line.
+    fn check_assumptions(&self) -> Result<(), String> {
+        let (start_row, start_col) = self.start;
+        if self.rows != self.cols {
+            return Err(format!("garden is {}x{}, not square", self.rows, self.cols));
+        }
+        if self.rows.is_multiple_of(2) || start_row != self.rows / 2 || start_col != self.cols / 2 {
+            return Err(format!("start {:?} is not in the centre", self.start));
+        }
+        let row_clear = (0..self.cols).all(|col| !self.is_rock(start_row as isize, col as isize));
+        let col_clear = (0..self.rows).all(|row| !self.is_rock(row as isize, start_col as isize));
+        if !row_clear || !col_clear {
+            return Err("the start's row and column are not clear of rocks".to_string());
+        }
+        Ok(())
     }
 
-    queue
-}
+    /// Once the walk has left the first copy of the garden, the number of reachable plots
+    /// after `remainder + k * size` steps is a quadratic in `k`. Three samples fit it, a fourth
+    /// confirms the fit. Returns `None` if the confirmation fails.
+    fn extrapolate(&self, steps: usize) -> Option<usize> {
+        let size = self.rows;
+        let remainder = steps % size;
+        let sample_steps = (1..=4).map(|k| remainder + k * size).collect::<Vec<_>>();
+        if steps <= sample_steps[3] {
+            return self.reachable(&[steps]).first().copied();
+        }
 
-// fn expand(matrix: &mut Matrix, (row, col): (usize, usize)) -> Vec<(usize, usize)> {
-//     matrix[row][col] = '.';
-//     let mut new_positions = Vec::new();
-//     [(-1isize, 0isize), (1, 0), (0, -1), (0, 1)]
-//         .iter()
-//         .for_each(|(row_shift, col_shift)| {
-//             let new_row = row as isize + *row_shift;
-//             let new_col = col as isize + *col_shift;
-//             if new_row < 0
-//                 || new_row >= matrix.len() as isize
-//                 || new_col < 0
-//                 || new_col >= matrix[0].len() as isize
-//             {
-//                 return;
-//             }
-//             if matrix[new_row as usize][new_col as usize] == '.' {
-//                 matrix[new_row as usize][new_col as usize] = 'O';
-//                 new_positions.push((new_row as usize, new_col as usize));
-//             }
-//         });
-//     new_positions
-// }
-
-fn wrap(a: isize, b: isize) -> usize {
-    (a.abs() % b) as usize
-}
+        let samples = self
+            .reachable(&sample_steps)
+            .iter()
+            .map(|count| *count as i64)
+            .collect::<Vec<_>>();
+        let quadratic = Polynomial::from_samples(&samples[..3]);
+        if quadratic.evaluate(3) != samples[3] as i128 {
+            return None;
+        }
+        let tiles = (steps / size - 1) as i64;
+        usize::try_from(quadratic.evaluate(tiles)).ok()
+    }
 
-fn expand_infinite(matrix: &Matrix, (row, col): (isize, isize)) -> Vec<(isize, isize)> {
-    let mut new_positions = Vec::new();
-    [(-1, 0), (1, 0), (0, -1), (0, 1)]
-        .iter()
-        .for_each(|(row_shift, col_shift)| {
-            let new_row = row + *row_shift;
-            let new_col = col + *col_shift;
-            if matrix[wrap(new_row, matrix.len() as isize)][wrap(new_col, matrix[0].len() as isize)]
-                == '.'
-            {
-                new_positions.push((new_row, new_col));
+    /// Breadth-first search from the start, counting the plots that can be stood on after
+    /// exactly each of `targets` steps.
+    ///
+    /// Every neighbour of a plot first reached at step `n` was reached at step `n - 1`, `n` or
+    /// `n + 1`, so only the last two frontiers need to be kept. Memory grows with the frontier,
+    /// not with the area covered.
+    fn reachable(&self, targets: &[usize]) -> Vec<usize> {
+        let max_steps = targets.iter().copied().max().unwrap_or(0);
+        let mut counts = vec![0; targets.len()];
+        // plots can be revisited by stepping back and forth, so parity is all that matters
+        let mut by_parity = [1, 0];
+        let mut previous: FastSet<(isize, isize)> = FastSet::default();
+        let mut frontier: FastSet<(isize, isize)> = FastSet::default();
+        frontier.insert((self.start.0 as isize, self.start.1 as isize));
+
+        for step in 0..=max_steps {
+            for (count, target) in counts.iter_mut().zip(targets) {
+                if *target == step {
+                    *count = by_parity[step % 2];
+                }
             }
-        });
-    new_positions
+            if step == max_steps {
+                break;
+            }
+
+            let mut next = FastSet::default();
+            for &(row, col) in &frontier {
+                for neighbour in [
+                    (row - 1, col),
+                    (row + 1, col),
+                    (row, col - 1),
+                    (row, col + 1),
+                ] {
+                    if !previous.contains(&neighbour)
+                        && !frontier.contains(&neighbour)
+                        && !self.is_rock(neighbour.0, neighbour.1)
+                    {
+                        next.insert(neighbour);
+                    }
+                }
+            }
+            by_parity[(step + 1) % 2] += next.len();
+            previous = std::mem::replace(&mut frontier, next);
+        }
+
+        counts
+    }
 }
 
 #[cfg(test)]
@@ -125,6 +170,29 @@ mod tests {
     #[test]
     fn test_part_two() {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, None);
+        assert_eq!(result, Some(167004));
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        // an open garden satisfies every assumption, and from the centre the walk is a diamond
+        let garden = Garden::parse(".....\n.....\n..S..\n.....\n.....\n").unwrap();
+        assert_eq!(garden.check_assumptions(), Ok(()));
+        let steps = 103;
+        assert_eq!(garden.extrapolate(steps), Some((steps + 1) * (steps + 1)));
+        assert_eq!(garden.reachable(&[10, 11]), vec![121, 144]);
+
+        let example = Garden::parse(&advent_of_code::template::read_file("examples", DAY));
+        assert!(example.unwrap().check_assumptions().is_err());
+    }
+
+    #[test]
+    fn test_reachable_far_beyond_the_grid() {
+        // the example is covered up to 500 steps by `test_part_two`
+        let open = Garden::parse(".....\n.....\n..S..\n.....\n.....\n").unwrap();
+        assert_eq!(
+            open.reachable(&[0, 200, 201]),
+            vec![1, 201 * 201, 202 * 202]
+        );
     }
 }
@@ -0,0 +1,233 @@
+use std::hash::Hash;
+
+use itertools::Itertools;
+
+use super::hash::FastMap;
+use super::math::chinese_remainder;
+
+/// The states visited by iterating a function until one of them repeats.
+///
+/// States `0..prefix` are only seen once, after that the sequence loops every `period` steps.
+#[derive(Debug, Clone)]
+pub struct Cycle<T> {
+    pub prefix: usize,
+    pub period: usize,
+    states: Vec<T>,
+}
+
+impl<T> Cycle<T> {
+    /// The state after applying the step function `n` times to the initial state.
+    pub fn nth_state(&self, n: usize) -> &T {
+        if n < self.states.len() {
+            return &self.states[n];
+        }
+        &self.states[self.prefix + (n - self.prefix) % self.period]
+    }
+
+    /// Every distinct state, in the order it was first reached.
+    pub fn states(&self) -> &[T] {
+        &self.states
+    }
+
+    /// The steps whose state matches `predicate`, for use with [`first_common_hit`].
+    pub fn hits(&self, mut predicate: impl FnMut(&T) -> bool) -> Hits {
+        Hits {
+            prefix: self.prefix,
+            period: self.period,
+            positions: (0..self.states.len())
+                .filter(|n| predicate(&self.states[*n]))
+                .collect(),
+        }
+    }
+}
+
+/// The positions at which an eventually periodic sequence has some property. From `prefix`
+/// onwards position `n` behaves like `n - period`, so only positions below `prefix + period`
+/// are stored, in increasing order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hits {
+    pub prefix: usize,
+    pub period: usize,
+    pub positions: Vec<usize>,
+}
+
+impl Hits {
+    pub fn contains(&self, n: usize) -> bool {
+        let n = if n < self.prefix {
+            n
+        } else {
+            self.prefix + (n - self.prefix) % self.period
+        };
+        self.positions.binary_search(&n).is_ok()
+    }
+
+    /// Whether the property still holds somewhere once the sequence loops.
+    pub fn recurs(&self) -> bool {
+        self.positions
+            .last()
+            .is_some_and(|last| *last >= self.prefix)
+    }
+}
+
+/// The first position `n >= from` contained in every one of `hits`.
+///
+/// Positions before every sequence has started looping are checked one by one. After that
+/// each sequence hits on a fixed set of residues modulo its period, and every combination of
+/// them is lined up with the CRT.
+pub fn first_common_hit(hits: &[Hits], from: usize) -> Option<usize> {
+    let settled = hits.iter().map(|h| h.prefix).max().unwrap_or(0);
+    if let Some(n) = (from..settled).find(|n| hits.iter().all(|h| h.contains(*n))) {
+        return Some(n);
+    }
+
+    let lowest = settled.max(from);
+    hits.iter()
+        .map(|h| {
+            h.positions
+                .iter()
+                .filter(|n| **n >= h.prefix)
+                .map(|n| ((n % h.period) as i64, h.period as i64))
+                .collect_vec()
+        })
+        .multi_cartesian_product()
+        .filter_map(|congruences| chinese_remainder(&congruences))
+        .map(|(residue, modulus)| {
+            let (residue, modulus) = (residue as usize, modulus as usize);
+            if residue >= lowest {
+                residue
+            } else {
+                residue + (lowest - residue).div_ceil(modulus) * modulus
+            }
+        })
+        .min()
+}
+
+/// Iterates `step` from `initial` until a state repeats, remembering every state on the way.
+///
+/// `step` is called exactly once for each distinct state, in the order they are reached.
+pub fn find_cycle<T, F>(initial: T, step: F) -> Cycle<T>
+where
+    T: Hash + Eq + Clone,
+    F: FnMut(&T) -> T,
+{
+    find_cycle_within(initial, usize::MAX, step).expect("a state repeats before usize::MAX steps")
+}
+
+/// Like [`find_cycle`], but gives up and returns `None` after `limit` distinct states.
+pub fn find_cycle_within<T, F>(initial: T, limit: usize, mut step: F) -> Option<Cycle<T>>
+where
+    T: Hash + Eq + Clone,
+    F: FnMut(&T) -> T,
+{
+    let mut seen = FastMap::default();
+    let mut states = Vec::new();
+    let mut current = initial;
+
+    loop {
+        if let Some(&prefix) = seen.get(&current) {
+            let period = states.len() - prefix;
+            return Some(Cycle {
+                prefix,
+                period,
+                states,
+            });
+        }
+        if states.len() == limit {
+            return None;
+        }
+        seen.insert(current.clone(), states.len());
+        let next = step(&current);
+        states.push(current);
+        current = next;
+    }
+}
+
+/// Brent's algorithm: finds `(prefix, period)` keeping only two states in memory, at the cost
+/// of re-running `step`. Useful when states are large or not hashable.
+pub fn brent<T, F>(initial: T, mut step: F) -> (usize, usize)
+where
+    T: Eq + Clone,
+    F: FnMut(&T) -> T,
+{
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+
+    (prefix, period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 2 -> ...
+    fn step(x: &u32) -> u32 {
+        if *x == 4 {
+            2
+        } else {
+            x + 1
+        }
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let cycle = find_cycle(0, step);
+        assert_eq!((cycle.prefix, cycle.period), (2, 3));
+        assert_eq!(*cycle.nth_state(3), 3);
+        assert_eq!(*cycle.nth_state(5), 2);
+        assert_eq!(*cycle.nth_state(1_000_000_000), 4);
+        assert!(find_cycle_within(0, 4, step).is_none());
+    }
+
+    #[test]
+    fn test_first_common_hit() {
+        // hits at 2, 5, 8, ... and at 1, 3, 5, ...
+        let threes = find_cycle(0, |x| (x + 1) % 3).hits(|x| *x == 2);
+        let odds = Hits {
+            prefix: 1,
+            period: 2,
+            positions: vec![1],
+        };
+        assert!(threes.contains(8) && !threes.contains(9));
+        assert!(odds.contains(7) && !odds.contains(0));
+        assert_eq!(
+            first_common_hit(&[threes.clone(), odds.clone()], 0),
+            Some(5)
+        );
+        assert_eq!(first_common_hit(&[threes.clone(), odds], 6), Some(11));
+
+        // a hit in the lead-in that never comes back
+        let once = find_cycle(0, |x| if *x < 3 { x + 1 } else { 1 }).hits(|x| *x == 0);
+        assert!(!once.recurs());
+        assert_eq!(first_common_hit(&[once.clone(), threes], 1), None);
+        assert_eq!(first_common_hit(&[once], 0), Some(0));
+    }
+
+    #[test]
+    fn test_brent() {
+        assert_eq!(brent(0, step), (2, 3));
+        assert_eq!(brent(7, |x| (x * 3) % 7), (1, 1));
+    }
+}
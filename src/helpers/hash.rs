@@ -0,0 +1,97 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasherDefault, Hasher};
+
+// the multiplier used by rustc's FxHasher
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// The non-cryptographic hasher rustc uses internally. Much faster than SipHash on small
+/// keys, at the price of no protection against crafted collisions, which we don't need here.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FxHasher {
+    hash: u64,
+}
+
+impl FxHasher {
+    #[inline]
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add_to_hash(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let mut remainder = chunks.remainder();
+        if remainder.len() >= 4 {
+            self.add_to_hash(u32::from_le_bytes(remainder[..4].try_into().unwrap()) as u64);
+            remainder = &remainder[4..];
+        }
+        for &byte in remainder {
+            self.add_to_hash(byte as u64);
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+pub type FxBuildHasher = BuildHasherDefault<FxHasher>;
+
+/// A `HashMap` using `FxHasher`. Create it with `FastMap::default()`.
+pub type FastMap<K, V> = HashMap<K, V, FxBuildHasher>;
+
+/// A `HashSet` using `FxHasher`. Create it with `FastSet::default()`.
+pub type FastSet<T> = HashSet<T, FxBuildHasher>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::BuildHasher;
+
+    #[test]
+    fn test_fast_collections() {
+        let mut map = FastMap::default();
+        map.insert("broadcaster", 1);
+        map.insert("rx", 2);
+        assert_eq!(map.get("rx"), Some(&2));
+
+        let set = (0..1_000).map(|i| (i, i * 2)).collect::<FastSet<_>>();
+        assert_eq!(set.len(), 1_000);
+        assert!(set.contains(&(10, 20)));
+
+        let hasher = FxBuildHasher::default();
+        assert_eq!(hasher.hash_one("abc"), hasher.hash_one("abc"));
+        assert_ne!(hasher.hash_one("abc"), hasher.hash_one("abd"));
+    }
+}
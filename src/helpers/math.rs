@@ -7,9 +7,89 @@ pub fn lowest_common_multiple(nums: &[usize]) -> usize {
     a * b / greatest_common_divisor(a, b)
 }
 
-fn greatest_common_divisor(a: usize, b: usize) -> usize {
+pub fn greatest_common_divisor(a: usize, b: usize) -> usize {
     if b == 0 {
         return a;
     }
     greatest_common_divisor(b, a % b)
 }
+
+/// The largest `r` such that `r * r <= n`. Newton's iteration started from a power of two
+/// above the root only ever decreases, and stops once it reaches the root.
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let bits = u128::BITS - n.leading_zeros();
+    let mut root = 1u128 << bits.div_ceil(2);
+    loop {
+        let next = (root + n / root) / 2;
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
+}
+
+/// Solves the system `x ≡ residue (mod modulus)` for every pair, where the moduli don't need
+/// to be coprime. Returns the smallest non-negative solution and the combined modulus, or
+/// `None` when the congruences contradict each other.
+pub fn chinese_remainder(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    congruences
+        .iter()
+        .try_fold((0i128, 1i128), |(residue, modulus), &(r, m)| {
+            let (r, m) = (r as i128, m as i128);
+            let (divisor, inverse, _) = extended_gcd(modulus, m);
+            if (r - residue) % divisor != 0 {
+                return None;
+            }
+            let combined = modulus / divisor * m;
+            let step = ((r - residue) / divisor * inverse).rem_euclid(m / divisor);
+            Some(((residue + modulus * step).rem_euclid(combined), combined))
+        })
+        .and_then(|(residue, modulus)| Some((residue.try_into().ok()?, modulus.try_into().ok()?)))
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y = g = gcd(a, b)`.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - a / b * y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isqrt() {
+        let roots = [0, 1, 2, 3, 4, 15, 16, 17].map(isqrt);
+        assert_eq!(roots, [0, 1, 1, 1, 2, 3, 4, 4]);
+        let root = u32::MAX as u128;
+        assert_eq!(isqrt(root * root), root);
+        assert_eq!(isqrt(root * root - 1), root - 1);
+        assert_eq!(isqrt(u64::MAX as u128), root);
+        let root = u64::MAX as u128;
+        assert_eq!(isqrt(root * root), root);
+        assert_eq!(isqrt(root * root - 1), root - 1);
+        assert_eq!(isqrt(u128::MAX), root);
+        for n in [1 << 127, (1 << 127) + 12345, u128::MAX / 3] {
+            let root = isqrt(n);
+            assert!(root * root <= n && (root + 1).checked_mul(root + 1).is_none_or(|s| s > n));
+        }
+    }
+
+    #[test]
+    fn test_chinese_remainder() {
+        assert_eq!(
+            chinese_remainder(&[(2, 3), (3, 5), (2, 7)]),
+            Some((23, 105))
+        );
+        // moduli sharing a factor
+        assert_eq!(chinese_remainder(&[(3, 4), (5, 6)]), Some((11, 12)));
+        assert_eq!(chinese_remainder(&[(0, 4), (1, 6)]), None);
+        assert_eq!(chinese_remainder(&[]), Some((0, 1)));
+    }
+}
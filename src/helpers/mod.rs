@@ -1,2 +1,8 @@
+pub mod cycle;
+pub mod hash;
 pub mod math;
 pub mod matrix;
+pub mod parse;
+pub mod poly;
+pub mod polygon;
+pub mod rng;
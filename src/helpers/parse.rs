@@ -0,0 +1,266 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// Integer types `ints` and `Cursor::int` know how to read.
+pub trait Int: FromStr + Copy {
+    const SIGNED: bool;
+}
+
+macro_rules! impl_int {
+    ($signed:expr, $($t:ty),*) => {
+        $(impl Int for $t {
+            const SIGNED: bool = $signed;
+        })*
+    };
+}
+
+impl_int!(true, i8, i16, i32, i64, i128, isize);
+impl_int!(false, u8, u16, u32, u64, u128, usize);
+
+/// Every integer in `input`, ignoring whatever separates them.
+///
+/// For signed types a `-` directly in front of the digits is part of the number unless it
+/// follows another digit, so `x=-3` gives `-3` but the range `2-4` gives `2` and `4`.
+/// Numbers that don't fit in `T` are skipped.
+pub fn ints<T: Int>(input: &str) -> impl Iterator<Item = T> + '_ {
+    let bytes = input.as_bytes();
+    let mut position = 0;
+    std::iter::from_fn(move || loop {
+        while position < bytes.len() && !bytes[position].is_ascii_digit() {
+            position += 1;
+        }
+        if position == bytes.len() {
+            return None;
+        }
+        let mut start = position;
+        if T::SIGNED
+            && start > 0
+            && bytes[start - 1] == b'-'
+            && (start < 2 || !bytes[start - 2].is_ascii_digit())
+        {
+            start -= 1;
+        }
+        while position < bytes.len() && bytes[position].is_ascii_digit() {
+            position += 1;
+        }
+        if let Ok(value) = input[start..position].parse() {
+            return Some(value);
+        }
+    })
+}
+
+/// Blocks of lines separated by one or more blank lines.
+pub fn sections(input: &str) -> impl Iterator<Item = &str> {
+    let mut rest = input;
+    std::iter::from_fn(move || {
+        rest = rest.trim_start_matches(['\r', '\n']);
+        if rest.is_empty() {
+            return None;
+        }
+        let mut end = rest.len();
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim().is_empty() {
+                end = offset;
+                break;
+            }
+            offset += line.len();
+        }
+        let (section, remaining) = rest.split_at(end);
+        rest = remaining;
+        Some(section.trim_end_matches(['\r', '\n']))
+    })
+}
+
+/// Splits `key: value` into its trimmed halves.
+pub fn key_value(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(':')?;
+    Some((key.trim(), value.trim()))
+}
+
+/// Splits `name{body}` into `name` and `body`.
+pub fn braced(line: &str) -> Option<(&str, &str)> {
+    let (name, body) = line.trim().split_once('{')?;
+    Some((name.trim(), body.strip_suffix('}')?))
+}
+
+/// A parse failure pointing at a 1-based line and column of the original input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+pub type ParseResult<T> = Result<T, ParseError>;
+
+/// A position in some input text with small parsers that advance it on success.
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, position: 0 }
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rest().is_empty()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Builds an error at the current position.
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        let consumed = &self.input[..self.position];
+        let line = consumed.matches('\n').count() + 1;
+        let line_start = consumed.rfind('\n').map_or(0, |i| i + 1);
+        ParseError {
+            line,
+            column: consumed[line_start..].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+
+    /// Consumes the longest prefix whose characters satisfy `predicate`, possibly empty.
+    pub fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let end = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.position += end;
+        &rest[..end]
+    }
+
+    /// Skips spaces and tabs, but not newlines.
+    pub fn spaces(&mut self) {
+        self.take_while(|c| c == ' ' || c == '\t');
+    }
+
+    pub fn tag(&mut self, tag: &str) -> ParseResult<()> {
+        if self.rest().starts_with(tag) {
+            self.position += tag.len();
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {tag:?}")))
+        }
+    }
+
+    /// A non-empty run of ASCII letters.
+    pub fn word(&mut self) -> ParseResult<&'a str> {
+        match self.take_while(|c| c.is_ascii_alphabetic()) {
+            "" => Err(self.error("expected a word")),
+            word => Ok(word),
+        }
+    }
+
+    pub fn int<T: Int>(&mut self) -> ParseResult<T> {
+        let start = *self;
+        let rest = self.rest();
+        let sign = usize::from(T::SIGNED && rest.starts_with('-'));
+        let digits = rest[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - sign);
+        if digits == 0 {
+            return Err(self.error("expected an integer"));
+        }
+        self.position += sign + digits;
+        rest[..sign + digits]
+            .parse()
+            .map_err(|_| start.error("integer out of range"))
+    }
+
+    /// Everything up to `delimiter`, consuming the delimiter too.
+    pub fn until(&mut self, delimiter: &str) -> ParseResult<&'a str> {
+        let rest = self.rest();
+        let end = rest
+            .find(delimiter)
+            .ok_or_else(|| self.error(format!("expected {delimiter:?}")))?;
+        self.position += end + delimiter.len();
+        Ok(&rest[..end])
+    }
+
+    /// Runs `parser`, rewinding to the current position if it fails.
+    pub fn optional<T>(&mut self, parser: impl FnOnce(&mut Self) -> ParseResult<T>) -> Option<T> {
+        let start = *self;
+        parser(self).map_err(|_| *self = start).ok()
+    }
+
+    /// One or more `item`s separated by `separator`.
+    pub fn separated<T>(
+        &mut self,
+        separator: &str,
+        mut item: impl FnMut(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<Vec<T>> {
+        let mut items = vec![item(self)?];
+        while self.optional(|cursor| cursor.tag(separator)).is_some() {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    /// Fails unless all input has been consumed, ignoring a trailing newline.
+    pub fn end(&mut self) -> ParseResult<()> {
+        self.take_while(|c| c == '\r' || c == '\n');
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error("unexpected trailing input"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(
+            ints::<i64>("x=-3, y=12 2-4").collect::<Vec<_>>(),
+            vec![-3, 12, 2, 4]
+        );
+        assert_eq!(ints::<u8>("-3 300 7").collect::<Vec<_>>(), vec![3, 7]);
+        assert_eq!(
+            sections("a\nb\n\n\nc\r\n\r\nd\n").collect::<Vec<_>>(),
+            vec!["a\nb", "c", "d"]
+        );
+        assert_eq!(key_value("Card 1: 41 48"), Some(("Card 1", "41 48")));
+        assert_eq!(braced("px{a<2006:qkq,rfg}"), Some(("px", "a<2006:qkq,rfg")));
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut cursor = Cursor::new("Game 3: 8 green, 6 blue\nGame x");
+        cursor.tag("Game ").unwrap();
+        assert_eq!(cursor.int::<u32>(), Ok(3));
+        cursor.tag(": ").unwrap();
+        let draws = cursor
+            .separated(", ", |cursor| {
+                let count = cursor.int::<u32>()?;
+                cursor.spaces();
+                Ok((count, cursor.word()?))
+            })
+            .unwrap();
+        assert_eq!(draws, vec![(8, "green"), (6, "blue")]);
+        cursor.tag("\nGame ").unwrap();
+        let error = cursor.int::<u32>().unwrap_err();
+        assert_eq!((error.line, error.column), (2, 6));
+        assert_eq!(error.to_string(), "2:6: expected an integer");
+    }
+}
@@ -0,0 +1,127 @@
+/// A polynomial through integer samples taken at `x = 0, 1, ..., n - 1`, stored in
+/// Newton forward-difference form: `f(x) = sum(Δᵏf(0) * C(x, k))`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    differences: Vec<i128>,
+}
+
+impl Polynomial {
+    /// Builds the interpolating polynomial of degree `< samples.len()`.
+    pub fn from_samples(samples: &[i64]) -> Self {
+        let mut row = samples.iter().map(|&s| s as i128).collect::<Vec<_>>();
+        let mut differences = Vec::with_capacity(row.len());
+        while let Some(&first) = row.first() {
+            differences.push(first);
+            row = row.windows(2).map(|w| w[1] - w[0]).collect();
+        }
+        // trailing zero differences don't contribute, trimming them gives the real degree
+        while differences.len() > 1 && differences.last() == Some(&0) {
+            differences.pop();
+        }
+
+        Self { differences }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.differences.len().saturating_sub(1)
+    }
+
+    /// Evaluates the polynomial at any integer `x`, including `x < 0` and `x` far past the
+    /// sampled range.
+    pub fn evaluate(&self, x: i64) -> i128 {
+        self.checked_evaluate(x)
+            .expect("polynomial value overflows an i128")
+    }
+
+    /// Like [`Polynomial::evaluate`], but returns `None` if an intermediate value overflows.
+    pub fn checked_evaluate(&self, x: i64) -> Option<i128> {
+        let x = x as i128;
+        let mut binomial = 1i128;
+        let mut result = 0i128;
+        for (k, difference) in self.differences.iter().enumerate() {
+            if k > 0 {
+                let k = k as i128;
+                // C(x, k) = C(x, k - 1) * (x - k + 1) / k, the division is always exact
+                binomial = binomial.checked_mul(x - k + 1)? / k;
+            }
+            result = result.checked_add(difference.checked_mul(binomial)?)?;
+        }
+        Some(result)
+    }
+}
+
+/// Extrapolates equally spaced samples (at `x = 0, 1, ...`) to an arbitrary `x`.
+pub fn extrapolate(samples: &[i64], x: i64) -> i128 {
+    Polynomial::from_samples(samples).evaluate(x)
+}
+
+/// Lagrange interpolation through arbitrary `(x, y)` points, evaluated exactly at `x`.
+/// Returns `None` if the points share an `x` or the value at `x` is not an integer.
+pub fn lagrange(points: &[(i64, i64)], x: i64) -> Option<i128> {
+    let x = x as i128;
+    let (mut numerator, mut denominator) = (0i128, 1i128);
+    for (i, &(xi, yi)) in points.iter().enumerate() {
+        let (mut term_num, mut term_den) = (yi as i128, 1i128);
+        for (j, &(xj, _)) in points.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            let den = xi as i128 - xj as i128;
+            if den == 0 {
+                return None;
+            }
+            term_num *= x - xj as i128;
+            term_den *= den;
+            let divisor = gcd(term_num, term_den);
+            term_num /= divisor;
+            term_den /= divisor;
+        }
+        numerator = numerator * term_den + term_num * denominator;
+        denominator *= term_den;
+        let divisor = gcd(numerator, denominator);
+        numerator /= divisor;
+        denominator /= divisor;
+    }
+
+    (numerator % denominator == 0).then_some(numerator / denominator)
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extrapolate() {
+        // f(x) = 3x² - 2x + 7
+        let samples = [7, 8, 15, 28];
+        let polynomial = Polynomial::from_samples(&samples);
+        assert_eq!(polynomial.degree(), 2);
+        assert_eq!(polynomial.evaluate(10), 287);
+        assert_eq!(polynomial.evaluate(-3), 40);
+        assert_eq!(extrapolate(&[1, 3, 6, 10, 15, 21], 6), 28);
+
+        let powers = (0..8i64).map(|x| x.pow(7)).collect::<Vec<_>>();
+        assert_eq!(
+            Polynomial::from_samples(&powers).checked_evaluate(i64::MAX),
+            None
+        );
+    }
+
+    #[test]
+    fn test_lagrange() {
+        // same polynomial sampled at uneven positions
+        let points = [(-1, 12), (2, 15), (5, 72)];
+        assert_eq!(lagrange(&points, 10), Some(287));
+        assert_eq!(lagrange(&points, 0), Some(7));
+        assert_eq!(lagrange(&[(0, 0), (2, 1)], 1), None);
+        assert_eq!(lagrange(&[(1, 0), (1, 1)], 1), None);
+    }
+}
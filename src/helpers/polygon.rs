@@ -0,0 +1,89 @@
+// Exact lattice polygon measurements built on the shoelace formula and Pick's theorem
+// https://en.wikipedia.org/wiki/Shoelace_formula
+// https://en.wikipedia.org/wiki/Pick%27s_theorem
+
+use super::math::greatest_common_divisor;
+
+/// A lattice point as `(row, col)`.
+pub type Point = (i64, i64);
+
+/// Turns a run of `(direction, length)` moves, starting from the origin, into the polygon's
+/// vertices. Directions are unit steps such as `(0, 1)` for right or `(-1, 0)` for up.
+pub fn vertices_from_moves<I>(moves: I) -> Vec<Point>
+where
+    I: IntoIterator<Item = (Point, i64)>,
+{
+    let mut current = (0, 0);
+    let mut vertices = vec![current];
+    for ((row_step, col_step), length) in moves {
+        current = (current.0 + row_step * length, current.1 + col_step * length);
+        vertices.push(current);
+    }
+    // a closed walk ends where it started, no need to keep the origin twice
+    if vertices.len() > 1 && vertices.first() == vertices.last() {
+        vertices.pop();
+    }
+    vertices
+}
+
+/// Twice the signed area of the polygon, positive for counter-clockwise vertices in `(x, y)`
+/// order. Keeping it doubled means it is always an exact integer.
+pub fn double_signed_area(vertices: &[Point]) -> i64 {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|((row_a, col_a), (row_b, col_b))| row_a * col_b - row_b * col_a)
+        .sum()
+}
+
+/// Number of lattice points lying on the polygon's edges.
+pub fn boundary_points(vertices: &[Point]) -> i64 {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|((row_a, col_a), (row_b, col_b))| {
+            greatest_common_divisor(
+                (row_b - row_a).unsigned_abs() as usize,
+                (col_b - col_a).unsigned_abs() as usize,
+            ) as i64
+        })
+        .sum()
+}
+
+/// Number of lattice points strictly inside the polygon, via Pick's theorem `A = I + B/2 - 1`.
+pub fn interior_points(vertices: &[Point]) -> i64 {
+    (double_signed_area(vertices).abs() - boundary_points(vertices) + 2) / 2
+}
+
+/// Number of lattice points inside the polygon or on its edges.
+pub fn enclosed_points(vertices: &[Point]) -> i64 {
+    interior_points(vertices) + boundary_points(vertices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let vertices = vertices_from_moves([((0, 1), 4), ((1, 0), 4), ((0, -1), 4), ((-1, 0), 4)]);
+        assert_eq!(vertices, vec![(0, 0), (0, 4), (4, 4), (4, 0)]);
+        assert_eq!(double_signed_area(&vertices).abs(), 32);
+        assert_eq!(boundary_points(&vertices), 16);
+        assert_eq!(interior_points(&vertices), 9);
+        assert_eq!(enclosed_points(&vertices), 25);
+    }
+
+    #[test]
+    fn test_diagonal_edges() {
+        let triangle = [(0, 0), (0, 6), (3, 3)];
+        assert_eq!(double_signed_area(&triangle).abs(), 18);
+        assert_eq!(boundary_points(&triangle), 12);
+        assert_eq!(interior_points(&triangle), 4);
+        let reversed = triangle.iter().rev().copied().collect::<Vec<_>>();
+        assert_eq!(
+            double_signed_area(&reversed),
+            -double_signed_area(&triangle)
+        );
+    }
+}
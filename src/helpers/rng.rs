@@ -0,0 +1,43 @@
+/// A xorshift64 generator, for reproducible random picks in tests and debugging output. Far
+/// too predictable for anything else.
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // an all-zero state would only ever produce zeroes
+        Self { state: seed | 1 }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A number in `0..bound`, slightly biased towards small ones unless `bound` is a power
+    /// of two.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xorshift() {
+        let mut rng = XorShift::new(0);
+        let first = (0..3).map(|_| rng.next_u64()).collect::<Vec<_>>();
+        assert_eq!(
+            first,
+            [1082269761, 1152992998833853505, 11177516664432764457]
+        );
+        assert!((0..100).all(|_| rng.below(6) < 6));
+        assert_eq!(XorShift::new(0).next_u64(), XorShift::new(1).next_u64());
+    }
+}
@@ -22,6 +22,7 @@ mod args {
             time: bool,
             dhat: bool,
             submit: Option<u8>,
+            params: Vec<String>,
         },
         All {
             release: bool,
@@ -53,6 +54,7 @@ mod args {
                 submit: args.opt_value_from_str("--submit")?,
                 time: args.contains("--time"),
                 dhat: args.contains("--dhat"),
+                params: args.values_from_str("--param")?,
             },
             Some(x) => {
                 eprintln!("Unknown command: {x}");
@@ -95,7 +97,8 @@ fn main() {
                 time,
                 dhat,
                 submit,
-            } => solve::handle(day, release, time, dhat, submit),
+                params,
+            } => solve::handle(day, release, time, dhat, submit, &params),
         },
     };
 }
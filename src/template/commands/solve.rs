@@ -2,7 +2,14 @@ use std::process::{Command, Stdio};
 
 use crate::template::Day;
 
-pub fn handle(day: Day, release: bool, time: bool, dhat: bool, submit_part: Option<u8>) {
+pub fn handle(
+    day: Day,
+    release: bool,
+    time: bool,
+    dhat: bool,
+    submit_part: Option<u8>,
+    params: &[String],
+) {
     let mut cmd_args = vec!["run".to_string(), "--bin".to_string(), day.to_string()];
 
     if dhat {
@@ -27,6 +34,11 @@ pub fn handle(day: Day, release: bool, time: bool, dhat: bool, submit_part: Opti
         cmd_args.push("--time".to_string());
     }
 
+    for param in params {
+        cmd_args.push("--param".to_string());
+        cmd_args.push(param.clone());
+    }
+
     let mut cmd = Command::new("cargo")
         .args(&cmd_args)
         .stdout(Stdio::inherit())
@@ -1,4 +1,4 @@
-use std::{env, fs};
+use std::{env, fs, str::FromStr, sync::Mutex};
 
 pub mod aoc_cli;
 pub mod commands;
@@ -33,6 +33,64 @@ pub fn read_file_part(folder: &str, day: Day, part: u8) -> String {
     f.expect("could not open input file")
 }
 
+/// Names of the parameters the solution has asked for so far.
+static REQUESTED_PARAMS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Every `--param <name>=<value>` pair passed to the binary, in order.
+fn passed_params() -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut args = env::args();
+    while let Some(arg) = args.next() {
+        if arg != "--param" {
+            continue;
+        }
+        match args.next() {
+            Some(param) => match param.split_once('=') {
+                Some((key, value)) => params.push((key.to_string(), value.to_string())),
+                None => eprintln!("warning: ignoring --param {param}, expected <name>=<value>"),
+            },
+            None => eprintln!("warning: ignoring --param without a value"),
+        }
+    }
+    params
+}
+
+/// Looks up a `--param <name>=<value>` passed to the `solve` command, so a solution can be run
+/// with other settings than its defaults. Returns `None` if the parameter is missing, and
+/// warns before returning `None` if its value doesn't parse.
+#[must_use]
+pub fn param<T: FromStr>(name: &str) -> Option<T> {
+    let first_request = {
+        let mut requested = REQUESTED_PARAMS.lock().unwrap();
+        let first_request = !requested.iter().any(|requested| requested == name);
+        if first_request {
+            requested.push(name.to_string());
+        }
+        first_request
+    };
+
+    let (_, value) = passed_params().into_iter().find(|(key, _)| key == name)?;
+    let parsed = value.parse().ok();
+    // `--time` runs a part many times, one warning is enough
+    if parsed.is_none() && first_request {
+        eprintln!(
+            "warning: ignoring --param {name}={value}, it is not a valid {}",
+            std::any::type_name::<T>()
+        );
+    }
+    parsed
+}
+
+/// Warns about every `--param` the solution never asked for, which is most likely a typo.
+pub fn check_params() {
+    let requested = REQUESTED_PARAMS.lock().unwrap();
+    for (key, _) in passed_params() {
+        if !requested.contains(&key) {
+            eprintln!("warning: --param {key} is not read by this solution");
+        }
+    }
+}
+
 /// Creates the constant `DAY` and sets up the input and runner for each part.
 ///
 /// The optional, second parameter (1 or 2) allows you to only run a single part of the solution.
@@ -60,6 +118,7 @@ macro_rules! solution {
             use $crate::template::runner::*;
             let input = $crate::template::read_file("inputs", DAY);
             $( run_part($func, &input, DAY, $part); )*
+            $crate::template::check_params();
         }
     };
 }